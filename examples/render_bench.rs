@@ -0,0 +1,176 @@
+use mursic::{
+    note::NoteKind,
+    num::{NaturalRatio, Real},
+    pitch::{Key, Pitch},
+    song::{PlayableSongBuilder, Song, SongBuilder},
+    source::Source,
+    tempo::{Dot, NoteValue, TimeSignature},
+    wave::SawWaveBuilder,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(
+        &self,
+        ptr: *mut u8,
+        layout: Layout,
+        new_size: usize,
+    ) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "render_bench",
+    about = "Compare per-sample, block and parallel song rendering"
+)]
+struct Options {
+    #[structopt(long, default_value = "1024")]
+    block: usize,
+    #[structopt(long, default_value = "4")]
+    threads: usize,
+    #[structopt(long, default_value = "64")]
+    repeats: usize,
+}
+
+fn make_song(repeats: usize) -> Song {
+    let progression = [
+        (Key::C, Key::E, Key::G),
+        (Key::A, Key::C, Key::E),
+        (Key::F, Key::A, Key::C),
+        (Key::G, Key::B, Key::D),
+    ];
+
+    let mut builder = SongBuilder::default();
+    builder
+        .bpm(NoteValue::Quarter, NaturalRatio::from(140))
+        .signature(TimeSignature { numer: 4, denom: NoteValue::Quarter })
+        .note_value(NoteValue::Quarter)
+        .dot(Dot::None);
+
+    for _ in 0 .. repeats {
+        for &(root, third, fifth) in &progression {
+            let bass = Pitch { octave: 3, key: root };
+            builder
+                .note_kind(NoteKind::Plain)
+                .pitch(bass)
+                .note()
+                .pitch(Pitch { octave: 5, key: root })
+                .note()
+                .note_group()
+                .pitch(Pitch { octave: 5, key: third })
+                .note()
+                .note_kind(NoteKind::Ligature)
+                .pitch(bass)
+                .note()
+                .note_group()
+                .note()
+                .pitch(Pitch { octave: 5, key: third })
+                .note()
+                .note_kind(NoteKind::Plain)
+                .pitch(Pitch { octave: 5, key: fifth })
+                .note()
+                .note_group()
+                .pitch(Pitch { octave: 6, key: root })
+                .note()
+                .note_kind(NoteKind::Ligature)
+                .pitch(bass)
+                .note()
+                .note_group()
+                .compass();
+        }
+    }
+
+    builder.clear_finish()
+}
+
+fn report(name: &str, samples: usize, elapsed: Duration, allocations: usize) {
+    println!(
+        "{}: {} samples in {:?}, {} allocations",
+        name, samples, elapsed, allocations
+    );
+}
+
+fn main() {
+    let options = Options::from_args();
+    let song = make_song(options.repeats.max(1));
+    let instrument = SawWaveBuilder::default();
+    let builder = PlayableSongBuilder::default();
+
+    let playable = builder.finish(song.clone(), instrument.clone());
+    let allocations = ALLOCATIONS.load(Relaxed);
+    let start = Instant::now();
+    let mut samples = 0;
+    let mut sum = 0.0;
+    for sample in playable {
+        samples += 1;
+        sum += sample;
+    }
+    let per_sample = start.elapsed();
+    let allocations = ALLOCATIONS.load(Relaxed) - allocations;
+    report("next()", samples, per_sample, allocations);
+
+    let mut playable = builder.finish(song.clone(), instrument.clone());
+    let mut buf = vec![0.0; options.block.max(1)];
+    let allocations = ALLOCATIONS.load(Relaxed);
+    let start = Instant::now();
+    let mut samples = 0;
+    let mut block_sum = 0.0;
+    loop {
+        let len = playable.fill(&mut buf);
+        samples += len;
+        block_sum += buf[.. len].iter().sum::<Real>();
+        if len < buf.len() {
+            break;
+        }
+    }
+    let per_block = start.elapsed();
+    let allocations = ALLOCATIONS.load(Relaxed) - allocations;
+    report(&format!("fill({})", buf.len()), samples, per_block, allocations);
+    println!(
+        "speedup: {:.2}x",
+        per_sample.as_secs_f64() / per_block.as_secs_f64()
+    );
+
+    let allocations = ALLOCATIONS.load(Relaxed);
+    let start = Instant::now();
+    let rendered = builder.render_parallel(&song, instrument, options.threads);
+    let parallel = start.elapsed();
+    let allocations = ALLOCATIONS.load(Relaxed) - allocations;
+    report(
+        &format!("render_parallel({})", options.threads),
+        rendered.len(),
+        parallel,
+        allocations,
+    );
+    println!(
+        "speedup: {:.2}x",
+        per_block.as_secs_f64() / parallel.as_secs_f64()
+    );
+
+    let parallel_sum = rendered.iter().sum::<Real>();
+    println!("checksums: {} {} {}", sum, block_sum, parallel_sum);
+}
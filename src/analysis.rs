@@ -0,0 +1,45 @@
+mod image;
+mod meter;
+mod onset;
+mod pitch;
+mod spectrum;
+mod transcribe;
+mod waveform;
+
+pub use image::{Image, Rgb};
+pub use meter::{Levels, Meter, MeterHandle, Tap};
+pub use onset::{OnsetDetector, Onsets, TempoEstimator};
+pub use pitch::{PitchDetector, PitchEstimate, PitchTrack};
+pub use spectrum::{Fft, Spectrogram, Stft, StftBuilder, Window};
+pub use transcribe::Transcriber;
+pub use waveform::{ScopeBuilder, WaveformBuilder};
+
+use crate::{num::Real, source::Source};
+
+const ANALYSIS_BLOCK: usize = 1024;
+
+fn read_mono<S>(source: &mut S, channels: usize) -> Option<Real>
+where
+    S: Source,
+{
+    let mut sum = source.next()?;
+    for _ in 1 .. channels {
+        sum += source.next().unwrap_or(0.0);
+    }
+    Some(sum / channels as Real)
+}
+
+pub fn measure<S>(mut source: S) -> Levels
+where
+    S: Source,
+{
+    let mut meter = Meter::new(source.channels(), source.sample_rate());
+    let mut buf = [0.0 as Real; ANALYSIS_BLOCK];
+    loop {
+        let len = source.fill(&mut buf);
+        meter.push_all(&buf[.. len]);
+        if len < buf.len() {
+            break meter.levels();
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+const STORED_BLOCK: usize = 65535;
+
+pub type Rgb = [u8; 3];
+
+fn crc32(bytes: &[u8], mut crc: u32) -> u32 {
+    crc = !crc;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0 .. 8 {
+            crc =
+                if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+fn write_chunk<W>(target: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()>
+where
+    W: Write,
+{
+    target.write_all(&(data.len() as u32).to_be_bytes())?;
+    target.write_all(kind)?;
+    target.write_all(data)?;
+    target.write_all(&crc32(data, crc32(kind, 0)).to_be_bytes())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self { width, height, pixels: vec![background; width * height] }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Rgb] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Rgb> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    pub fn vertical_line(
+        &mut self,
+        x: usize,
+        top: usize,
+        bottom: usize,
+        color: Rgb,
+    ) {
+        let (top, bottom) = (top.min(bottom), top.max(bottom));
+        for y in top ..= bottom {
+            self.set(x, y, color);
+        }
+    }
+
+    pub fn horizontal_line(&mut self, y: usize, color: Rgb) {
+        for x in 0 .. self.width {
+            self.set(x, y, color);
+        }
+    }
+
+    pub fn write_ppm<W>(&self, mut target: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write!(target, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let bytes: Vec<u8> = self.pixels.iter().flatten().copied().collect();
+        target.write_all(&bytes)
+    }
+
+    pub fn write_png<W>(&self, mut target: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+
+        let mut data = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(STORED_BLOCK).peekable();
+        if blocks.peek().is_none() {
+            data.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+        }
+        while let Some(block) = blocks.next() {
+            let len = block.len() as u16;
+            data.push(if blocks.peek().is_none() { 1 } else { 0 });
+            data.extend_from_slice(&len.to_le_bytes());
+            data.extend_from_slice(&(!len).to_le_bytes());
+            data.extend_from_slice(block);
+        }
+        data.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        target.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_chunk(&mut target, b"IHDR", &header)?;
+        write_chunk(&mut target, b"IDAT", &data)?;
+        write_chunk(&mut target, b"IEND", &[])
+    }
+
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let png = matches!(
+            path.extension(),
+            Some(ext) if ext.eq_ignore_ascii_case("png")
+        );
+        let mut target = BufWriter::new(File::create(path)?);
+        if png {
+            self.write_png(&mut target)?;
+        } else {
+            self.write_ppm(&mut target)?;
+        }
+        target.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32, Image};
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b"IEND", 0), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789", 0), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn ppm_has_header_and_pixels() {
+        let mut image = Image::new(2, 3, [1, 2, 3]);
+        image.set(1, 2, [4, 5, 6]);
+        let mut ppm = Vec::new();
+        image.write_ppm(&mut ppm).unwrap();
+
+        let header = b"P6\n2 3\n255\n";
+        assert_eq!(&ppm[.. header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 2 * 3 * 3);
+        assert_eq!(&ppm[ppm.len() - 6 ..], &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn png_chunks_are_framed() {
+        let image = Image::new(3, 2, [255, 0, 0]);
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+
+        assert_eq!(&png[.. 8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12 .. 16], b"IHDR");
+        assert_eq!(&png[16 .. 24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+        assert_eq!(
+            &png[png.len() - 12 ..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]
+        );
+    }
+}
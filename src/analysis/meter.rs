@@ -0,0 +1,488 @@
+use crate::{
+    num::{amplitude_to_db, real::consts::PI, Real},
+    source::{Seekable, Source},
+};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+const OVERSAMPLING: usize = 4;
+const INTERP_TAPS: usize = 12;
+const STEP_MILLIS: u32 = 100;
+const MOMENTARY_STEPS: usize = 4;
+const SHORT_TERM_STEPS: usize = 30;
+const ABSOLUTE_GATE: Real = -70.0;
+const RELATIVE_GATE: Real = -10.0;
+
+fn power_to_lufs(power: Real) -> Real {
+    -0.691 + 10.0 * power.log10()
+}
+
+fn lufs_to_power(lufs: Real) -> Real {
+    Real::powf(10.0, (lufs + 0.691) / 10.0)
+}
+
+fn mean(values: &[Real]) -> Real {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<Real>() / values.len() as Real
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Biquad {
+    b0: Real,
+    b1: Real,
+    b2: Real,
+    a1: Real,
+    a2: Real,
+    x1: Real,
+    x2: Real,
+    y1: Real,
+    y2: Real,
+}
+
+impl Biquad {
+    #[allow(clippy::excessive_precision)]
+    fn high_shelf(sample_rate: Real) -> Self {
+        let gain: Real = 3.999843853973347;
+        let q: Real = 0.7071752369554196;
+        let k = (PI * 1681.974450955533 / sample_rate).tan();
+        let vh = Real::powf(10.0, gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        Self {
+            b0: (vh + vb * k / q + k * k) / a0,
+            b1: 2.0 * (k * k - vh) / a0,
+            b2: (vh - vb * k / q + k * k) / a0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            ..Self::default()
+        }
+    }
+
+    #[allow(clippy::excessive_precision)]
+    fn high_pass(sample_rate: Real) -> Self {
+        let q: Real = 0.5003270373238773;
+        let k = (PI * 38.13547087602444 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Self {
+            b0: 1.0,
+            b1: -2.0,
+            b2: 1.0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            ..Self::default()
+        }
+    }
+
+    fn process(&mut self, input: Real) -> Real {
+        let output = self.b0 * input + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = input;
+        self.y2 = self.y1;
+        self.y1 = output;
+        output
+    }
+}
+
+#[derive(Debug, Clone)]
+struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeighting {
+    fn new(sample_rate: Real) -> Self {
+        Self {
+            shelf: Biquad::high_shelf(sample_rate),
+            high_pass: Biquad::high_pass(sample_rate),
+        }
+    }
+
+    fn process(&mut self, input: Real) -> Real {
+        self.high_pass.process(self.shelf.process(input))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TruePeak {
+    coefs: [[Real; INTERP_TAPS]; OVERSAMPLING],
+    history: Vec<[Real; INTERP_TAPS]>,
+    cursor: usize,
+    peak: Real,
+}
+
+impl TruePeak {
+    fn new(channels: usize) -> Self {
+        let half = (INTERP_TAPS / 2) as Real;
+        let mut coefs = [[0.0; INTERP_TAPS]; OVERSAMPLING];
+        for (phase, row) in coefs.iter_mut().enumerate() {
+            let offset = phase as Real / OVERSAMPLING as Real;
+            for (tap, coef) in row.iter_mut().enumerate() {
+                let x = offset + half - 1.0 - tap as Real;
+                let sinc =
+                    if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) };
+                let window = 0.5 * (1.0 + (PI * x / half).cos());
+                *coef = sinc * window;
+            }
+        }
+        Self {
+            coefs,
+            history: vec![[0.0; INTERP_TAPS]; channels],
+            cursor: 0,
+            peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, channel: usize, input: Real) {
+        let history = &mut self.history[channel];
+        history[self.cursor] = input;
+        for row in &self.coefs {
+            let mut sum = 0.0;
+            for (tap, coef) in row.iter().enumerate() {
+                let index = (self.cursor + INTERP_TAPS - tap) % INTERP_TAPS;
+                sum += coef * history[index];
+            }
+            self.peak = self.peak.max(sum.abs());
+        }
+        if channel + 1 == self.history.len() {
+            self.cursor = (self.cursor + 1) % INTERP_TAPS;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Levels {
+    pub peak: Real,
+    pub rms: Real,
+    pub true_peak: Real,
+    pub momentary: Real,
+    pub short_term: Real,
+    pub max_short_term: Real,
+    pub integrated: Real,
+}
+
+impl Levels {
+    pub fn peak_db(&self) -> Real {
+        amplitude_to_db(self.peak)
+    }
+
+    pub fn rms_db(&self) -> Real {
+        amplitude_to_db(self.rms)
+    }
+
+    pub fn true_peak_db(&self) -> Real {
+        amplitude_to_db(self.true_peak)
+    }
+
+    pub fn crest_factor(&self) -> Real {
+        if self.rms > 0.0 {
+            self.peak / self.rms
+        } else {
+            1.0
+        }
+    }
+
+    pub fn crest_factor_db(&self) -> Real {
+        amplitude_to_db(self.crest_factor())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Meter {
+    weights: Vec<Real>,
+    filters: Vec<KWeighting>,
+    true_peak: TruePeak,
+    channel: usize,
+    peak: Real,
+    square_sum: Real,
+    samples: usize,
+    step_frames: usize,
+    step_sum: Real,
+    step_len: usize,
+    steps: Vec<Real>,
+}
+
+impl Meter {
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        let channels = usize::from(channels.max(1));
+        let weights = (0 .. channels)
+            .map(|channel| match (channels, channel) {
+                (6, 3) => 0.0,
+                (6, 4) | (6, 5) => 1.41,
+                _ => 1.0,
+            })
+            .collect();
+        let rate = sample_rate.max(1) as Real;
+        Self {
+            weights,
+            filters: vec![KWeighting::new(rate); channels],
+            true_peak: TruePeak::new(channels),
+            channel: 0,
+            peak: 0.0,
+            square_sum: 0.0,
+            samples: 0,
+            step_frames: (sample_rate as usize * STEP_MILLIS as usize / 1000)
+                .max(1),
+            step_sum: 0.0,
+            step_len: 0,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, sample: Real) {
+        let channel = self.channel;
+        self.peak = self.peak.max(sample.abs());
+        self.square_sum += sample * sample;
+        self.samples += 1;
+        self.true_peak.process(channel, sample);
+
+        let weighted = self.filters[channel].process(sample);
+        self.step_sum += self.weights[channel] * weighted * weighted;
+
+        self.channel += 1;
+        if self.channel == self.weights.len() {
+            self.channel = 0;
+            self.step_len += 1;
+            if self.step_len == self.step_frames {
+                self.steps.push(self.step_sum / self.step_frames as Real);
+                self.step_sum = 0.0;
+                self.step_len = 0;
+            }
+        }
+    }
+
+    pub fn push_all(&mut self, samples: &[Real]) {
+        for &sample in samples {
+            self.push(sample);
+        }
+    }
+
+    fn window(&self, steps: usize) -> Real {
+        let start = self.steps.len().saturating_sub(steps);
+        power_to_lufs(mean(&self.steps[start ..]))
+    }
+
+    fn max_short_term(&self) -> Real {
+        let window = SHORT_TERM_STEPS.min(self.steps.len()).max(1);
+        self.steps
+            .windows(window)
+            .map(|steps| power_to_lufs(mean(steps)))
+            .fold(Real::NEG_INFINITY, Real::max)
+    }
+
+    fn integrated(&self) -> Real {
+        let blocks: Vec<Real> =
+            self.steps.windows(MOMENTARY_STEPS).map(mean).collect();
+        let absolute = lufs_to_power(ABSOLUTE_GATE);
+        let gated: Vec<Real> =
+            blocks.iter().copied().filter(|&block| block > absolute).collect();
+        let relative =
+            lufs_to_power(power_to_lufs(mean(&gated)) + RELATIVE_GATE);
+        let gated: Vec<Real> =
+            gated.into_iter().filter(|&block| block > relative).collect();
+        power_to_lufs(mean(&gated))
+    }
+
+    pub fn levels(&self) -> Levels {
+        let rms = if self.samples > 0 {
+            (self.square_sum / self.samples as Real).sqrt()
+        } else {
+            0.0
+        };
+        Levels {
+            peak: self.peak,
+            rms,
+            true_peak: self.true_peak.peak.max(self.peak),
+            momentary: self.window(MOMENTARY_STEPS),
+            short_term: self.window(SHORT_TERM_STEPS),
+            max_short_term: self.max_short_term(),
+            integrated: self.integrated(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MeterHandle {
+    meter: Arc<Mutex<Meter>>,
+}
+
+impl MeterHandle {
+    fn lock(&self) -> MutexGuard<'_, Meter> {
+        self.meter.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    pub fn levels(&self) -> Levels {
+        self.lock().levels()
+    }
+
+    pub fn meter(&self) -> Meter {
+        self.lock().clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tap<S>
+where
+    S: Source,
+{
+    inner: S,
+    handle: MeterHandle,
+}
+
+impl<S> Tap<S>
+where
+    S: Source,
+{
+    pub(crate) fn new(inner: S) -> Self {
+        let meter = Meter::new(inner.channels(), inner.sample_rate());
+        Self {
+            inner,
+            handle: MeterHandle { meter: Arc::new(Mutex::new(meter)) },
+        }
+    }
+
+    pub fn handle(&self) -> MeterHandle {
+        self.handle.clone()
+    }
+
+    pub fn levels(&self) -> Levels {
+        self.handle.levels()
+    }
+}
+
+impl<S> Iterator for Tap<S>
+where
+    S: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.inner.next()?;
+        self.handle.lock().push(sample);
+        Some(sample)
+    }
+}
+
+impl<S> Source for Tap<S>
+where
+    S: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.inner.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let len = self.inner.fill(buf);
+        self.handle.lock().push_all(&buf[.. len]);
+        len
+    }
+}
+
+impl<S> Seekable for Tap<S>
+where
+    S: Seekable,
+{
+    fn seek(&mut self, frame: usize) {
+        self.inner.seek(frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Meter;
+    use crate::{
+        num::{real::consts::PI, Real},
+        source::{SamplesBuffer, Source},
+    };
+
+    fn sine(freq: Real, amplitude: Real, secs: usize) -> Vec<Real> {
+        (0 .. secs * 48000)
+            .map(|i| amplitude * (2.0 * PI * freq * i as Real / 48000.0).sin())
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_sine_reads_minus_three_lufs() {
+        let mut meter = Meter::new(1, 48000);
+        meter.push_all(&sine(1000.0, 1.0, 5));
+        let levels = meter.levels();
+
+        assert!((levels.integrated + 3.01).abs() < 0.05);
+        assert!((levels.momentary + 3.01).abs() < 0.05);
+        assert!((levels.short_term + 3.01).abs() < 0.05);
+        assert!((levels.peak - 1.0).abs() < 1e-3);
+        assert!((levels.rms_db() + 3.01).abs() < 0.01);
+    }
+
+    #[test]
+    fn gating_ignores_silence_and_quiet_passages() {
+        let mut meter = Meter::new(1, 48000);
+        meter.push_all(&sine(1000.0, 1.0, 3));
+        meter.push_all(&vec![0.0; 10 * 48000]);
+        meter.push_all(&sine(1000.0, 0.01, 5));
+        let levels = meter.levels();
+
+        // The three blocks overlapping the end of the tone still pass
+        // both gates; everything after them is gated out.
+        let expected = -3.01 + 10.0 * Real::log10(28.5 / 30.0);
+        assert!((levels.integrated - expected).abs() < 0.05);
+        assert!((levels.max_short_term + 3.01).abs() < 0.1);
+        assert!(levels.momentary < -40.0);
+    }
+
+    #[test]
+    fn true_peak_finds_inter_sample_peaks() {
+        let samples = (0 .. 48000)
+            .map(|i| (PI / 2.0 * (i % 4) as Real + PI / 4.0).sin())
+            .collect::<Vec<_>>();
+        let mut meter = Meter::new(1, 48000);
+        meter.push_all(&samples);
+        let levels = meter.levels();
+
+        assert!((levels.peak - Real::sqrt(0.5)).abs() < 1e-3);
+        assert!(levels.true_peak > 0.95);
+        assert!(levels.true_peak_db() > levels.peak_db() + 2.5);
+    }
+
+    #[test]
+    fn tap_reports_the_same_levels_as_meter() {
+        let samples = sine(440.0, 0.5, 2)
+            .into_iter()
+            .zip(sine(97.0, 0.8, 2))
+            .flat_map(|(left, right)| vec![left, right])
+            .collect::<Vec<_>>();
+        let mut meter = Meter::new(2, 48000);
+        meter.push_all(&samples);
+
+        let mut tap = SamplesBuffer::new(2, 48000, samples).tap();
+        let handle = tap.handle();
+        let mut buf = [0.0; 333];
+        while tap.next().is_some() {
+            if tap.fill(&mut buf) < buf.len() {
+                break;
+            }
+        }
+
+        assert_eq!(tap.levels(), meter.levels());
+        assert_eq!(handle.levels(), meter.levels());
+        assert_eq!(handle.meter().levels(), meter.levels());
+    }
+}
@@ -0,0 +1,318 @@
+use super::{StftBuilder, Window};
+use crate::{
+    num::{Natural, NaturalRatio, Real},
+    source::{duration_frames, frames_duration, Source},
+};
+use std::time::Duration;
+
+const PRIOR_BPM: Real = 120.0;
+
+#[derive(Debug, Clone)]
+pub struct Onsets {
+    strength: Vec<Real>,
+    peaks: Vec<usize>,
+    hop: usize,
+    sample_rate: u32,
+}
+
+impl Onsets {
+    pub fn strength(&self) -> &[Real] {
+        &self.strength
+    }
+
+    pub fn frame_rate(&self) -> Real {
+        self.sample_rate as Real / self.hop as Real
+    }
+
+    pub fn frame_time(&self, frame: usize) -> Duration {
+        frames_duration(frame * self.hop, self.sample_rate)
+    }
+
+    pub fn times(&self) -> Vec<Duration> {
+        self.peaks.iter().map(|&frame| self.frame_time(frame)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OnsetDetector {
+    size: usize,
+    hop: usize,
+    threshold: Real,
+    min_gap: Duration,
+}
+
+impl Default for OnsetDetector {
+    fn default() -> Self {
+        Self {
+            size: 1024,
+            hop: 256,
+            threshold: 0.2,
+            min_gap: Duration::from_millis(50),
+        }
+    }
+}
+
+impl OnsetDetector {
+    pub fn size(&mut self, size: usize) -> &mut Self {
+        self.size = size.max(2).next_power_of_two();
+        self
+    }
+
+    pub fn hop(&mut self, hop: usize) -> &mut Self {
+        self.hop = hop.max(1);
+        self
+    }
+
+    pub fn threshold(&mut self, threshold: Real) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn min_gap(&mut self, min_gap: Duration) -> &mut Self {
+        self.min_gap = min_gap;
+        self
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_hop(&self) -> usize {
+        self.hop
+    }
+
+    pub fn get_threshold(&self) -> Real {
+        self.threshold
+    }
+
+    pub fn get_min_gap(&self) -> Duration {
+        self.min_gap
+    }
+
+    pub fn analyze<S>(&self, source: S) -> Onsets
+    where
+        S: Source,
+    {
+        let sample_rate = source.sample_rate();
+        let mut previous: Option<Vec<Real>> = None;
+        let mut strength = Vec::new();
+        let stft = StftBuilder::default()
+            .size(self.size)
+            .hop(self.hop)
+            .window(Window::Hann)
+            .centered(true)
+            .finish(source);
+
+        for bins in stft {
+            let current: Vec<Real> =
+                bins.iter().map(|bin| bin.norm().ln_1p()).collect();
+            let flux = match &previous {
+                Some(previous) => current
+                    .iter()
+                    .zip(previous)
+                    .map(|(curr, prev)| (curr - prev).max(0.0))
+                    .sum(),
+                None => current.iter().sum(),
+            };
+            strength.push(flux);
+            previous = Some(current);
+        }
+
+        let max = strength.iter().copied().fold(0.0, Real::max);
+        if max > 0.0 {
+            for value in &mut strength {
+                *value /= max;
+            }
+        }
+
+        let gap =
+            (duration_frames(self.min_gap, sample_rate) / self.hop).max(1);
+        let mut peaks: Vec<usize> = Vec::new();
+        for (frame, &value) in strength.iter().enumerate() {
+            let start = frame.saturating_sub(gap);
+            let end = (frame + gap + 1).min(strength.len());
+            let window = &strength[start .. end];
+            let local_max = window.iter().all(|&other| other <= value);
+            let mean = window.iter().sum::<Real>() / window.len() as Real;
+            let spaced = match peaks.last() {
+                Some(&last) => frame - last >= gap,
+                None => true,
+            };
+            if local_max && spaced && value > mean + self.threshold {
+                peaks.push(frame);
+            }
+        }
+
+        Onsets { strength, peaks, hop: self.hop, sample_rate }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TempoEstimator {
+    min_bpm: Real,
+    max_bpm: Real,
+    resolution: Natural,
+}
+
+impl Default for TempoEstimator {
+    fn default() -> Self {
+        Self { min_bpm: 60.0, max_bpm: 200.0, resolution: 1 }
+    }
+}
+
+impl TempoEstimator {
+    pub fn min_bpm(&mut self, min_bpm: Real) -> &mut Self {
+        self.min_bpm = min_bpm;
+        self
+    }
+
+    pub fn max_bpm(&mut self, max_bpm: Real) -> &mut Self {
+        self.max_bpm = max_bpm;
+        self
+    }
+
+    pub fn resolution(&mut self, resolution: Natural) -> &mut Self {
+        self.resolution = resolution.max(1);
+        self
+    }
+
+    pub fn get_min_bpm(&self) -> Real {
+        self.min_bpm
+    }
+
+    pub fn get_max_bpm(&self) -> Real {
+        self.max_bpm
+    }
+
+    pub fn get_resolution(&self) -> Natural {
+        self.resolution
+    }
+
+    pub fn estimate_real(&self, onsets: &Onsets) -> Option<Real> {
+        let frame_rate = onsets.frame_rate();
+        let min_lag =
+            (60.0 * frame_rate / self.max_bpm).floor().max(1.0) as usize;
+        let max_lag = (60.0 * frame_rate / self.min_bpm).ceil() as usize;
+        let raw = onsets.strength();
+        if max_lag + 1 >= raw.len() || min_lag > max_lag {
+            return None;
+        }
+
+        // Spread each peak over its neighbours so a beat period that falls
+        // between two frames still correlates with itself.
+        let strength: Vec<Real> = (0 .. raw.len())
+            .map(|frame| {
+                let start = frame.saturating_sub(1);
+                let end = (frame + 2).min(raw.len());
+                raw[start .. end].iter().sum::<Real>() / (end - start) as Real
+            })
+            .collect();
+
+        let mean = strength.iter().sum::<Real>() / strength.len() as Real;
+        let centered: Vec<Real> =
+            strength.iter().map(|value| value - mean).collect();
+        let correlation = |lag: usize| -> Real {
+            let sum: Real = centered
+                .iter()
+                .zip(&centered[lag ..])
+                .map(|(a, b)| a * b)
+                .sum();
+            let bpm = 60.0 * frame_rate / lag as Real;
+            let octaves = (bpm / PRIOR_BPM).log2();
+            let prior = (-0.5 * octaves * octaves).exp();
+            prior * sum / (centered.len() - lag) as Real
+        };
+
+        let scores: Vec<Real> =
+            (min_lag - 1 ..= max_lag + 1).map(correlation).collect();
+        let (index, best) = scores[1 .. scores.len() - 1]
+            .iter()
+            .enumerate()
+            .fold((0, Real::NEG_INFINITY), |(index, best), (curr, &score)| {
+                if score > best {
+                    (curr + 1, score)
+                } else {
+                    (index, best)
+                }
+            });
+        if best <= 0.0 {
+            return None;
+        }
+
+        let (left, right) = (scores[index - 1], scores[index + 1]);
+        let denom = left - 2.0 * best + right;
+        let offset =
+            if denom.abs() > 0.0 { 0.5 * (left - right) / denom } else { 0.0 };
+        let lag = (min_lag + index - 1) as Real + offset.clamp(-1.0, 1.0);
+        Some(60.0 * frame_rate / lag)
+    }
+
+    pub fn estimate(&self, onsets: &Onsets) -> Option<NaturalRatio> {
+        let bpm = self.estimate_real(onsets)?;
+        let scaled = (bpm * self.resolution as Real).round() as Natural;
+        Some(NaturalRatio::new(scaled, self.resolution))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OnsetDetector, TempoEstimator};
+    use crate::{
+        note::NoteKind,
+        num::{Natural, NaturalRatio},
+        pitch::{Key, Pitch},
+        song::{PlayableSongBuilder, Song, SongBuilder},
+        tempo::{Dot, NoteValue, TimeSignature},
+        wave::SawWaveBuilder,
+    };
+    use std::time::Duration;
+
+    fn repeated(bpm: Natural, compasses: usize) -> Song {
+        let mut builder = SongBuilder::default();
+        builder
+            .bpm(NoteValue::Quarter, NaturalRatio::from(bpm))
+            .signature(TimeSignature { numer: 4, denom: NoteValue::Quarter })
+            .note_value(NoteValue::Quarter)
+            .dot(Dot::None)
+            .note_kind(NoteKind::Plain)
+            .pitch(Pitch { octave: 4, key: Key::A });
+        for _ in 0 .. compasses {
+            for _ in 0 .. 4 {
+                builder.note().note_group();
+            }
+            builder.compass();
+        }
+        builder.clear_finish()
+    }
+
+    #[test]
+    fn onsets_follow_song_notes() {
+        let song = repeated(100, 2);
+        let nanos = song.compasses[0].note_groups[0].tempo.nanos();
+        let playable = PlayableSongBuilder::default()
+            .finish(song, SawWaveBuilder::default());
+        let detector = OnsetDetector::default();
+        let onsets = detector.analyze(playable);
+        let hop = Duration::from_secs(detector.get_hop() as u64) / 48000;
+
+        let times = onsets.times();
+        assert_eq!(times.len(), 8, "{:?}", times);
+        for (note, time) in times.into_iter().enumerate() {
+            let expected = nanos * NaturalRatio::from(note as Natural);
+            let expected = Duration::from_nanos(expected.to_integer() as u64);
+            let error = time.abs_diff(expected);
+            assert!(error <= hop, "{:?} != {:?}", time, expected);
+        }
+    }
+
+    #[test]
+    fn tempo_estimate_recovers_song_bpm() {
+        for &bpm in &[60, 100, 140] {
+            let playable = PlayableSongBuilder::default()
+                .finish(repeated(bpm, 4), SawWaveBuilder::default());
+            let onsets = OnsetDetector::default().analyze(playable);
+            let estimate = TempoEstimator::default().estimate(&onsets);
+            assert_eq!(estimate, Some(NaturalRatio::from(bpm)));
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use super::read_mono;
+use crate::{
+    num::Real,
+    pitch::Pitch,
+    source::{frames_duration, Source},
+};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitchEstimate {
+    pub time: Duration,
+    pub freq: Option<Real>,
+    pub clarity: Real,
+}
+
+impl PitchEstimate {
+    pub fn pitch(&self, a5: Real) -> Option<(Pitch, Real)> {
+        Pitch::from_freq(self.freq?, a5)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PitchDetector {
+    size: usize,
+    hop: usize,
+    threshold: Real,
+    min_freq: Real,
+    max_freq: Real,
+}
+
+impl Default for PitchDetector {
+    fn default() -> Self {
+        Self {
+            size: 2048,
+            hop: 512,
+            threshold: 0.15,
+            min_freq: 40.0,
+            max_freq: 2000.0,
+        }
+    }
+}
+
+impl PitchDetector {
+    pub fn size(&mut self, size: usize) -> &mut Self {
+        self.size = size.max(4);
+        self
+    }
+
+    pub fn hop(&mut self, hop: usize) -> &mut Self {
+        self.hop = hop.max(1);
+        self
+    }
+
+    pub fn threshold(&mut self, threshold: Real) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn min_freq(&mut self, min_freq: Real) -> &mut Self {
+        self.min_freq = min_freq;
+        self
+    }
+
+    pub fn max_freq(&mut self, max_freq: Real) -> &mut Self {
+        self.max_freq = max_freq;
+        self
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_hop(&self) -> usize {
+        self.hop
+    }
+
+    pub fn get_threshold(&self) -> Real {
+        self.threshold
+    }
+
+    pub fn get_min_freq(&self) -> Real {
+        self.min_freq
+    }
+
+    pub fn get_max_freq(&self) -> Real {
+        self.max_freq
+    }
+
+    fn lags(&self, sample_rate: u32) -> (usize, usize) {
+        let rate = sample_rate as Real;
+        let max_lag = ((rate / self.min_freq.max(1.0)).ceil() as usize)
+            .min(self.size / 2)
+            .max(2);
+        let min_lag = ((rate / self.max_freq.max(1.0)).floor() as usize)
+            .clamp(2, max_lag);
+        (min_lag, max_lag)
+    }
+
+    pub fn detect(
+        &self,
+        samples: &[Real],
+        sample_rate: u32,
+    ) -> Option<(Real, Real)> {
+        let (min_lag, max_lag) = self.lags(sample_rate);
+        if samples.len() <= max_lag + 1 {
+            return None;
+        }
+        let width = samples.len() - max_lag - 1;
+
+        let mut diffs = vec![1.0; max_lag + 2];
+        let mut running = 0.0;
+        for lag in 1 ..= max_lag + 1 {
+            let diff: Real = samples[.. width]
+                .iter()
+                .zip(&samples[lag .. lag + width])
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            running += diff;
+            diffs[lag] =
+                if running > 0.0 { diff * lag as Real / running } else { 1.0 };
+        }
+
+        let mut lag =
+            (min_lag ..= max_lag).find(|&lag| diffs[lag] < self.threshold)?;
+        while lag < max_lag && diffs[lag + 1] < diffs[lag] {
+            lag += 1;
+        }
+
+        let (left, center, right) =
+            (diffs[lag - 1], diffs[lag], diffs[lag + 1]);
+        let denom = left - 2.0 * center + right;
+        let offset =
+            if denom.abs() > 0.0 { 0.5 * (left - right) / denom } else { 0.0 };
+        let period = lag as Real + offset.clamp(-1.0, 1.0);
+        Some((sample_rate as Real / period, 1.0 - center))
+    }
+
+    pub fn finish<S>(&self, source: S) -> PitchTrack<S>
+    where
+        S: Source,
+    {
+        let mut track = PitchTrack {
+            channels: usize::from(source.channels().max(1)),
+            inner: source,
+            detector: self.clone(),
+            samples: vec![0.0; self.size],
+            fresh: 0,
+            position: 0,
+        };
+        track.read(0);
+        track
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PitchTrack<S>
+where
+    S: Source,
+{
+    inner: S,
+    detector: PitchDetector,
+    channels: usize,
+    samples: Vec<Real>,
+    fresh: usize,
+    position: usize,
+}
+
+impl<S> PitchTrack<S>
+where
+    S: Source,
+{
+    fn read(&mut self, start: usize) {
+        self.fresh = 0;
+        for index in start .. self.samples.len() {
+            self.samples[index] =
+                match read_mono(&mut self.inner, self.channels) {
+                    Some(sample) => {
+                        self.fresh += 1;
+                        sample
+                    },
+                    None => 0.0,
+                };
+        }
+    }
+}
+
+impl<S> Iterator for PitchTrack<S>
+where
+    S: Source,
+{
+    type Item = PitchEstimate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fresh == 0 {
+            return None;
+        }
+
+        let sample_rate = self.inner.sample_rate();
+        let detected = self.detector.detect(&self.samples, sample_rate);
+        let estimate = PitchEstimate {
+            time: frames_duration(self.position, sample_rate),
+            freq: detected.map(|(freq, _)| freq),
+            clarity: detected.map_or(0.0, |(_, clarity)| clarity),
+        };
+
+        let size = self.samples.len();
+        let hop = self.detector.hop.min(size);
+        self.samples.copy_within(hop .., 0);
+        self.read(size - hop);
+        self.position += hop;
+        Some(estimate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PitchDetector;
+    use crate::{
+        num::{real::consts::PI, Real},
+        pitch::{Key, Pitch},
+        source::SamplesBuffer,
+    };
+
+    fn sine(freq: Real, sample_rate: u32, len: usize) -> Vec<Real> {
+        (0 .. len)
+            .map(|n| {
+                0.6 * (2.0 * PI * freq * n as Real / sample_rate as Real).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_sine_frequencies() {
+        let detector = PitchDetector::default();
+        for &freq in &[82.41, 110.0, 261.63, 440.0, 987.77, 1760.0] {
+            let samples = sine(freq, 44100, 2048);
+            let (found, clarity) = detector.detect(&samples, 44100).unwrap();
+            assert!(
+                (found / freq - 1.0).abs() < 0.005,
+                "{} != {}",
+                found,
+                freq
+            );
+            assert!(clarity > 0.9);
+        }
+    }
+
+    #[test]
+    fn silence_has_no_pitch() {
+        let detector = PitchDetector::default();
+        assert_eq!(detector.detect(&[0.0; 2048], 44100), None);
+        assert_eq!(detector.detect(&[0.0; 16], 44100), None);
+    }
+
+    #[test]
+    fn track_follows_stereo_source() {
+        let mono = sine(220.0, 22050, 22050 / 2);
+        let stereo = mono.iter().flat_map(|&sample| vec![sample, sample]);
+        let source = SamplesBuffer::new(2, 22050, stereo.collect());
+        let estimates: Vec<_> = PitchDetector::default()
+            .size(1024)
+            .hop(1024)
+            .finish(source)
+            .collect();
+
+        assert!(estimates.len() >= 10);
+        for estimate in &estimates[.. 10] {
+            let (pitch, cents) = estimate.pitch(440.0).unwrap();
+            assert_eq!(pitch, Pitch { key: Key::A, octave: 4 });
+            assert!(cents.abs() < 10.0);
+        }
+    }
+}
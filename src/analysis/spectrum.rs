@@ -0,0 +1,437 @@
+use super::{read_mono, Image, Rgb};
+use crate::{
+    num::{amplitude_to_db, real::consts::PI, Real},
+    source::{frames_duration, Source},
+};
+use num::complex::Complex;
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+#[derive(Debug, Clone)]
+pub struct Fft {
+    size: usize,
+    twiddles: Vec<Complex<Real>>,
+    reversed: Vec<usize>,
+}
+
+impl Fft {
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two(), "FFT size must be a power of two");
+        let bits = size.trailing_zeros();
+        let twiddles = (0 .. size / 2)
+            .map(|k| {
+                Complex::from_polar(
+                    &1.0,
+                    &(-2.0 * PI * k as Real / size as Real),
+                )
+            })
+            .collect();
+        let reversed = (0 .. size)
+            .map(|i| {
+                if bits == 0 {
+                    0
+                } else {
+                    i.reverse_bits() >> (usize::BITS - bits)
+                }
+            })
+            .collect();
+        Self { size, twiddles, reversed }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn process(&self, buf: &mut [Complex<Real>]) {
+        assert_eq!(buf.len(), self.size, "buffer does not match FFT size");
+        for (i, &j) in self.reversed.iter().enumerate() {
+            if i < j {
+                buf.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= self.size {
+            let stride = self.size / len;
+            for chunk in buf.chunks_mut(len) {
+                let (even, odd) = chunk.split_at_mut(len / 2);
+                for (k, (even, odd)) in even.iter_mut().zip(odd).enumerate() {
+                    let twiddled = *odd * self.twiddles[k * stride];
+                    *odd = *even - twiddled;
+                    *even += twiddled;
+                }
+            }
+            len *= 2;
+        }
+    }
+
+    pub fn inverse(&self, buf: &mut [Complex<Real>]) {
+        for value in buf.iter_mut() {
+            *value = value.conj();
+        }
+        self.process(buf);
+        let scale = 1.0 / self.size as Real;
+        for value in buf.iter_mut() {
+            *value = value.conj() * scale;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl Window {
+    pub fn coefs(self, size: usize) -> Vec<Real> {
+        (0 .. size)
+            .map(|n| {
+                let phase = 2.0 * PI * n as Real / size as Real;
+                match self {
+                    Window::Rectangular => 1.0,
+                    Window::Hann => 0.5 - 0.5 * phase.cos(),
+                    Window::Hamming => 0.54 - 0.46 * phase.cos(),
+                    Window::Blackman => {
+                        0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos()
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Stft<S>
+where
+    S: Source,
+{
+    inner: S,
+    fft: Fft,
+    window: Vec<Real>,
+    hop: usize,
+    channels: usize,
+    samples: Vec<Real>,
+    fresh: usize,
+    scratch: Vec<Complex<Real>>,
+}
+
+impl<S> Stft<S>
+where
+    S: Source,
+{
+    fn read(&mut self, start: usize) {
+        self.fresh = 0;
+        for index in start .. self.samples.len() {
+            self.samples[index] =
+                match read_mono(&mut self.inner, self.channels) {
+                    Some(sample) => {
+                        self.fresh += 1;
+                        sample
+                    },
+                    None => 0.0,
+                };
+        }
+    }
+
+    pub fn bins(&self) -> usize {
+        self.fft.size() / 2 + 1
+    }
+}
+
+impl<S> Iterator for Stft<S>
+where
+    S: Source,
+{
+    type Item = Vec<Complex<Real>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fresh == 0 {
+            return None;
+        }
+
+        let norm = 2.0 / self.window.iter().sum::<Real>();
+        for ((value, sample), coef) in
+            self.scratch.iter_mut().zip(&self.samples).zip(&self.window)
+        {
+            *value = Complex::new(sample * coef * norm, 0.0);
+        }
+        self.fft.process(&mut self.scratch);
+        let bins = self.scratch[.. self.bins()].to_vec();
+
+        let size = self.samples.len();
+        let hop = self.hop.min(size);
+        self.samples.copy_within(hop .., 0);
+        self.read(size - hop);
+        Some(bins)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StftBuilder {
+    size: usize,
+    hop: usize,
+    window: Window,
+    centered: bool,
+}
+
+impl Default for StftBuilder {
+    fn default() -> Self {
+        Self { size: 2048, hop: 512, window: Window::Hann, centered: false }
+    }
+}
+
+impl StftBuilder {
+    pub fn size(&mut self, size: usize) -> &mut Self {
+        self.size = size.max(2).next_power_of_two();
+        self
+    }
+
+    pub fn hop(&mut self, hop: usize) -> &mut Self {
+        self.hop = hop.max(1);
+        self
+    }
+
+    pub fn window(&mut self, window: Window) -> &mut Self {
+        self.window = window;
+        self
+    }
+
+    pub fn centered(&mut self, centered: bool) -> &mut Self {
+        self.centered = centered;
+        self
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_hop(&self) -> usize {
+        self.hop
+    }
+
+    pub fn get_window(&self) -> Window {
+        self.window
+    }
+
+    pub fn get_centered(&self) -> bool {
+        self.centered
+    }
+
+    pub fn finish<S>(&self, source: S) -> Stft<S>
+    where
+        S: Source,
+    {
+        let mut stft = Stft {
+            channels: usize::from(source.channels().max(1)),
+            inner: source,
+            fft: Fft::new(self.size),
+            window: self.window.coefs(self.size),
+            hop: self.hop,
+            samples: vec![0.0; self.size],
+            fresh: 0,
+            scratch: vec![Complex::new(0.0, 0.0); self.size],
+        };
+        stft.read(if self.centered { self.size / 2 } else { 0 });
+        stft
+    }
+
+    pub fn spectrogram<S>(&self, source: S) -> Spectrogram
+    where
+        S: Source,
+    {
+        let sample_rate = source.sample_rate();
+        let frames = self
+            .finish(source)
+            .map(|bins| bins.iter().map(|bin| bin.norm()).collect())
+            .collect();
+        Spectrogram { frames, sample_rate, size: self.size, hop: self.hop }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Spectrogram {
+    frames: Vec<Vec<Real>>,
+    sample_rate: u32,
+    size: usize,
+    hop: usize,
+}
+
+impl Spectrogram {
+    pub fn frames(&self) -> &[Vec<Real>] {
+        &self.frames
+    }
+
+    pub fn bins(&self) -> usize {
+        self.size / 2 + 1
+    }
+
+    pub fn bin_freq(&self, bin: usize) -> Real {
+        bin as Real * self.sample_rate as Real / self.size as Real
+    }
+
+    pub fn frame_time(&self, frame: usize) -> Duration {
+        frames_duration(frame * self.hop, self.sample_rate)
+    }
+
+    pub fn magnitude(&self, frame: usize, freq: Real) -> Option<Real> {
+        let bin = (freq * self.size as Real / self.sample_rate as Real).round();
+        self.frames.get(frame)?.get(bin as usize).copied()
+    }
+
+    pub fn peak_freq(&self, frame: usize) -> Option<Real> {
+        let bins = self.frames.get(frame)?;
+        let (peak, _) = bins.iter().enumerate().skip(1).fold(
+            (0, 0.0),
+            |(best, max), (bin, &magnitude)| {
+                if magnitude > max {
+                    (bin, magnitude)
+                } else {
+                    (best, max)
+                }
+            },
+        );
+        if peak == 0
+            || peak + 1 >= bins.len()
+            || bins[peak - 1] <= 0.0
+            || bins[peak + 1] <= 0.0
+        {
+            return Some(self.bin_freq(peak));
+        }
+
+        let left = bins[peak - 1].ln();
+        let center = bins[peak].ln();
+        let right = bins[peak + 1].ln();
+        let denom = left - 2.0 * center + right;
+        let offset =
+            if denom.abs() > 0.0 { 0.5 * (left - right) / denom } else { 0.0 };
+        Some(
+            (peak as Real + offset) * self.sample_rate as Real
+                / self.size as Real,
+        )
+    }
+
+    pub fn write_csv<W>(&self, mut target: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write!(target, "time")?;
+        for bin in 0 .. self.bins() {
+            write!(target, ",{}", self.bin_freq(bin))?;
+        }
+        writeln!(target)?;
+        for (index, frame) in self.frames.iter().enumerate() {
+            write!(target, "{}", self.frame_time(index).as_secs_f64())?;
+            for magnitude in frame {
+                write!(target, ",{}", magnitude)?;
+            }
+            writeln!(target)?;
+        }
+        Ok(())
+    }
+
+    pub fn image(&self, floor_db: Real) -> Image {
+        let width = self.frames.len();
+        let height = self.bins();
+        let mut image = Image::new(width, height, [0; 3]);
+        for (x, frame) in self.frames.iter().enumerate() {
+            for (bin, &magnitude) in frame.iter().enumerate() {
+                let db = amplitude_to_db(magnitude).max(floor_db);
+                let level =
+                    if floor_db < 0.0 { 1.0 - db / floor_db } else { 1.0 };
+                image.set(x, height - 1 - bin, heat(level.clamp(0.0, 1.0)));
+            }
+        }
+        image
+    }
+
+    pub fn write_ppm<W>(&self, target: W, floor_db: Real) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.image(floor_db).write_ppm(target)
+    }
+}
+
+fn heat(level: Real) -> Rgb {
+    let channel = |value: Real| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [
+        channel(3.0 * level),
+        channel(3.0 * level - 1.0),
+        channel(3.0 * level - 2.0),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fft, Spectrogram, StftBuilder, Window};
+    use crate::{
+        num::{real::consts::PI, Real},
+        source::SamplesBuffer,
+    };
+    use num::complex::Complex;
+
+    fn sine(freq: Real, sample_rate: u32, len: usize) -> Vec<Real> {
+        (0 .. len)
+            .map(|n| (2.0 * PI * freq * n as Real / sample_rate as Real).sin())
+            .collect()
+    }
+
+    #[test]
+    fn fft_puts_sine_in_its_bin() {
+        let fft = Fft::new(64);
+        let mut buf: Vec<_> = sine(5.0, 64, 64)
+            .into_iter()
+            .map(|sample| Complex::new(sample, 0.0))
+            .collect();
+        fft.process(&mut buf);
+
+        for (bin, value) in buf.iter().enumerate() {
+            let expected = if bin == 5 || bin == 59 { 32.0 } else { 0.0 };
+            assert!((value.norm() - expected).abs() < 1e-4, "bin {}", bin);
+        }
+
+        fft.inverse(&mut buf);
+        for (value, sample) in buf.iter().zip(sine(5.0, 64, 64)) {
+            assert!((value.re - sample).abs() < 1e-4);
+            assert!(value.im.abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn peak_freq_interpolates_between_bins() {
+        let sample_rate = 8000;
+        let source =
+            SamplesBuffer::new(1, sample_rate, sine(440.0, 8000, 4096));
+        let spectrogram = StftBuilder::default()
+            .size(1024)
+            .hop(1024)
+            .window(Window::Hann)
+            .spectrogram(source);
+
+        assert_eq!(spectrogram.bins(), 513);
+        assert_eq!(spectrogram.bin_freq(57), 445.3125);
+        let peak = spectrogram.peak_freq(1).unwrap();
+        assert!((peak - 440.0).abs() < 0.5, "peak {}", peak);
+    }
+
+    #[test]
+    fn peak_freq_skips_zero_neighbours() {
+        let spectrogram = Spectrogram {
+            frames: vec![
+                vec![0.0, 0.0, 1.0, 0.0, 0.0],
+                vec![0.0, 0.5, 1.0, 0.0, 0.0],
+            ],
+            sample_rate: 800,
+            size: 8,
+            hop: 8,
+        };
+        assert_eq!(spectrogram.peak_freq(0), Some(200.0));
+        assert_eq!(spectrogram.peak_freq(1), Some(200.0));
+        assert_eq!(spectrogram.peak_freq(2), None);
+    }
+}
@@ -0,0 +1,309 @@
+use super::{OnsetDetector, PitchDetector, TempoEstimator};
+use crate::{
+    note::NoteKind,
+    num::{Natural, NaturalRatio, RatioExt, Real},
+    pitch::Pitch,
+    song::{Song, SongBuilder},
+    source::{SamplesBuffer, Source, WavSource},
+    tempo::{Dot, NoteValue, TimeSignature},
+};
+use std::path::Path;
+
+const NOTE_VALUES: [NoteValue; 7] = [
+    NoteValue::Whole,
+    NoteValue::Half,
+    NoteValue::Quarter,
+    NoteValue::Eighth,
+    NoteValue::Sixteenth,
+    NoteValue::ThirtySecond,
+    NoteValue::SixtyForth,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Event {
+    start: usize,
+    end: usize,
+    pitch: Option<Pitch>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Transcriber {
+    signature: TimeSignature,
+    bpm: Option<NaturalRatio>,
+    grid: NoteValue,
+    a5: Real,
+    pitch: PitchDetector,
+    onsets: OnsetDetector,
+    tempo: TempoEstimator,
+}
+
+impl Default for Transcriber {
+    fn default() -> Self {
+        Self {
+            signature: TimeSignature { numer: 4, denom: NoteValue::Quarter },
+            bpm: None,
+            grid: NoteValue::Sixteenth,
+            a5: 440.0,
+            pitch: PitchDetector::default(),
+            onsets: OnsetDetector::default(),
+            tempo: TempoEstimator::default(),
+        }
+    }
+}
+
+impl Transcriber {
+    pub fn signature(&mut self, signature: TimeSignature) -> &mut Self {
+        self.signature = signature;
+        self
+    }
+
+    pub fn bpm(&mut self, bpm: Option<NaturalRatio>) -> &mut Self {
+        self.bpm = bpm;
+        self
+    }
+
+    pub fn grid(&mut self, grid: NoteValue) -> &mut Self {
+        self.grid = grid;
+        self
+    }
+
+    pub fn a5_freq(&mut self, a5: Real) -> &mut Self {
+        self.a5 = a5;
+        self
+    }
+
+    pub fn pitch_detector(&mut self, pitch: PitchDetector) -> &mut Self {
+        self.pitch = pitch;
+        self
+    }
+
+    pub fn onset_detector(&mut self, onsets: OnsetDetector) -> &mut Self {
+        self.onsets = onsets;
+        self
+    }
+
+    pub fn tempo_estimator(&mut self, tempo: TempoEstimator) -> &mut Self {
+        self.tempo = tempo;
+        self
+    }
+
+    pub fn get_signature(&self) -> TimeSignature {
+        self.signature
+    }
+
+    pub fn get_bpm(&self) -> Option<NaturalRatio> {
+        self.bpm
+    }
+
+    pub fn get_grid(&self) -> NoteValue {
+        self.grid
+    }
+
+    pub fn get_a5_freq(&self) -> Real {
+        self.a5
+    }
+
+    pub fn get_pitch_detector(&self) -> &PitchDetector {
+        &self.pitch
+    }
+
+    pub fn get_onset_detector(&self) -> &OnsetDetector {
+        &self.onsets
+    }
+
+    pub fn get_tempo_estimator(&self) -> &TempoEstimator {
+        &self.tempo
+    }
+
+    pub fn transcribe_wav<P>(&self, path: P) -> Result<Song, hound::Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(self.transcribe(WavSource::open(path)?))
+    }
+
+    pub fn transcribe<S>(&self, source: S) -> Song
+    where
+        S: Source,
+    {
+        let buffer = SamplesBuffer::<Real>::from_source(source);
+        let sample_rate = buffer.sample_rate() as Real;
+        let onsets = self.onsets.analyze(buffer.clone());
+        let bpm = self
+            .bpm
+            .or_else(|| self.tempo.estimate(&onsets))
+            .unwrap_or_else(|| NaturalRatio::from(120));
+
+        let quarter = 60.0 / bpm.approx_to_real();
+        let slot = quarter * 4.0 / self.grid.numeric() as Real;
+        let to_slot = |secs: Real| (secs / slot).round().max(0.0) as usize;
+
+        let center = self.pitch.get_size() as Real / 2.0 / sample_rate;
+        let hop = self.pitch.get_hop() as Real / sample_rate;
+        let track: Vec<(Real, Option<Pitch>)> = self
+            .pitch
+            .finish(buffer)
+            .map(|estimate| {
+                let time = estimate.time.as_secs_f64() as Real + center;
+                (time, estimate.pitch(self.a5).map(|(pitch, _)| pitch))
+            })
+            .collect();
+
+        let mut runs: Vec<(Real, Real, Option<Pitch>)> = Vec::new();
+        for &(time, pitch) in &track {
+            match runs.last_mut() {
+                Some(run) if run.2 == pitch => run.1 = time + hop,
+                _ => runs.push((time, time + hop, pitch)),
+            }
+        }
+        runs.retain(|run| run.2.is_some() && run.1 - run.0 >= 2.0 * hop);
+
+        let mut events: Vec<Event> = Vec::new();
+        let mut onset_times =
+            onsets.times().into_iter().map(|time| time.as_secs_f64() as Real);
+        let mut next_onset = onset_times.next();
+        for (start, end, pitch) in runs {
+            let mut start = start;
+            while let Some(onset) = next_onset.filter(|&onset| onset < end) {
+                if onset > start + slot / 2.0 {
+                    events.push(Event {
+                        start: to_slot(start),
+                        end: to_slot(onset),
+                        pitch,
+                    });
+                    start = onset;
+                }
+                next_onset = onset_times.next();
+            }
+            events.push(Event {
+                start: to_slot(start),
+                end: to_slot(end),
+                pitch,
+            });
+        }
+
+        self.build(bpm, events)
+    }
+
+    fn build(&self, bpm: NaturalRatio, events: Vec<Event>) -> Song {
+        let grid = Natural::from(self.grid.numeric());
+        let compass_slots = (self.signature.ratio() * grid).ceil().to_integer();
+        let compass_slots = (compass_slots as usize).max(1);
+
+        let mut timeline: Vec<Event> = Vec::new();
+        for event in events {
+            let cursor = timeline.last().map_or(0, |last| last.end);
+            let start = event.start.max(cursor);
+            if event.end <= start {
+                continue;
+            }
+            if start > cursor {
+                timeline.push(Event { start: cursor, end: start, pitch: None });
+            }
+            timeline.push(Event { start, ..event });
+        }
+        let end = timeline.last().map_or(0, |last| last.end);
+        let total = end.div_ceil(compass_slots) * compass_slots;
+        if total > end {
+            timeline.push(Event { start: end, end: total, pitch: None });
+        }
+
+        let mut builder = SongBuilder::default();
+        builder.signature(self.signature).bpm(NoteValue::Quarter, bpm);
+        for event in timeline {
+            let mut kind = NoteKind::Plain;
+            let mut start = event.start;
+            while start < event.end {
+                let compass_end = (start / compass_slots + 1) * compass_slots;
+                let piece = event.end.min(compass_end) - start;
+                let (note_value, dot, slots) = self.largest_value(piece);
+                builder.note_value(note_value).dot(dot);
+                if let Some(pitch) = event.pitch {
+                    builder.pitch(pitch).note_kind(kind).note();
+                    kind = NoteKind::Ligature;
+                }
+                builder.note_group();
+                start += slots;
+                if start == compass_end {
+                    builder.compass();
+                }
+            }
+        }
+        builder.finish()
+    }
+
+    fn largest_value(&self, slots: usize) -> (NoteValue, Dot, usize) {
+        let grid = self.grid.numeric() as usize;
+        NOTE_VALUES
+            .iter()
+            .filter(|value| value.numeric() as usize <= grid)
+            .flat_map(|&value| {
+                let base = grid / value.numeric() as usize;
+                let dotted = if base >= 2 { base * 3 / 2 } else { 0 };
+                vec![(value, Dot::Single, dotted), (value, Dot::None, base)]
+            })
+            .find(|&(_, _, len)| len > 0 && len <= slots)
+            .unwrap_or((self.grid, Dot::None, 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transcriber;
+    use crate::{
+        note::NoteKind,
+        num::NaturalRatio,
+        pitch::{Key, Pitch},
+        song::{PlayableSongBuilder, SongBuilder},
+        tempo::{Dot, NoteValue, TimeSignature},
+        wave::SawWaveBuilder,
+    };
+
+    #[test]
+    fn transcribe_recovers_rendered_song() {
+        let melody = [
+            (Key::C, NoteValue::Quarter, false),
+            (Key::E, NoteValue::Quarter, false),
+            (Key::G, NoteValue::Half, true),
+            (Key::A, NoteValue::Whole, true),
+        ];
+        let mut builder = SongBuilder::default();
+        builder
+            .bpm(NoteValue::Quarter, NaturalRatio::from(140))
+            .signature(TimeSignature { numer: 4, denom: NoteValue::Quarter })
+            .dot(Dot::None)
+            .note_kind(NoteKind::Plain);
+        for &(key, note_value, compass_end) in &melody {
+            builder
+                .note_value(note_value)
+                .pitch(Pitch { octave: 5, key })
+                .note()
+                .note_group();
+            if compass_end {
+                builder.compass();
+            }
+        }
+        let song = builder.clear_finish();
+        let playable = PlayableSongBuilder::default()
+            .finish(song, SawWaveBuilder::default());
+
+        let transcribed = Transcriber::default().transcribe(playable);
+        let notes: Vec<_> = transcribed
+            .compasses
+            .iter()
+            .flat_map(|compass| &compass.note_groups)
+            .map(|group| {
+                let pitches: Vec<_> =
+                    group.notes.iter().map(|note| note.pitch).collect();
+                (pitches, group.tempo.note_value, group.tempo.dot)
+            })
+            .collect();
+        let expected: Vec<_> = melody
+            .iter()
+            .map(|&(key, note_value, _)| {
+                (vec![Pitch { octave: 5, key }], note_value, Dot::None)
+            })
+            .collect();
+        assert_eq!(notes, expected);
+    }
+}
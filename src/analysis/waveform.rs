@@ -0,0 +1,195 @@
+use super::{Image, Rgb};
+use crate::{num::Real, source::Source, wave::Wave};
+
+const BACKGROUND: Rgb = [16, 16, 24];
+const AXIS: Rgb = [56, 56, 72];
+const ENVELOPE: Rgb = [80, 160, 255];
+const TRACE: Rgb = [120, 255, 120];
+
+fn sample_y(sample: Real, top: usize, height: usize) -> usize {
+    let level = (1.0 - sample.clamp(-1.0, 1.0)) / 2.0;
+    top + (level * (height - 1) as Real).round() as usize
+}
+
+#[derive(Debug, Clone)]
+pub struct WaveformBuilder {
+    width: usize,
+    channel_height: usize,
+}
+
+impl Default for WaveformBuilder {
+    fn default() -> Self {
+        Self { width: 1024, channel_height: 128 }
+    }
+}
+
+impl WaveformBuilder {
+    pub fn width(&mut self, width: usize) -> &mut Self {
+        self.width = width.max(1);
+        self
+    }
+
+    pub fn channel_height(&mut self, channel_height: usize) -> &mut Self {
+        self.channel_height = channel_height.max(2);
+        self
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_channel_height(&self) -> usize {
+        self.channel_height
+    }
+
+    pub fn finish<S>(&self, source: S) -> Option<Image>
+    where
+        S: Source,
+    {
+        source.len()?;
+        let channels = usize::from(source.channels().max(1));
+        let samples: Vec<Real> = source.collect();
+        let frames = samples.len() / channels;
+        let height = self.channel_height;
+        let mut image = Image::new(self.width, height * channels, BACKGROUND);
+
+        for channel in 0 .. channels {
+            let top = channel * height;
+            image.horizontal_line(top + height / 2, AXIS);
+            for x in 0 .. self.width {
+                let start = x * frames / self.width;
+                let end = ((x + 1) * frames / self.width).max(start + 1);
+                let column = samples
+                    .iter()
+                    .skip(start * channels + channel)
+                    .step_by(channels)
+                    .take(end.min(frames).saturating_sub(start));
+                let (min, max) = column.fold(
+                    (Real::INFINITY, Real::NEG_INFINITY),
+                    |(min, max), &sample| (min.min(sample), max.max(sample)),
+                );
+                if min <= max {
+                    image.vertical_line(
+                        x,
+                        sample_y(max, top, height),
+                        sample_y(min, top, height),
+                        ENVELOPE,
+                    );
+                }
+            }
+        }
+
+        Some(image)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScopeBuilder {
+    width: usize,
+    height: usize,
+    cycles: Real,
+}
+
+impl Default for ScopeBuilder {
+    fn default() -> Self {
+        Self { width: 512, height: 256, cycles: 1.0 }
+    }
+}
+
+impl ScopeBuilder {
+    pub fn width(&mut self, width: usize) -> &mut Self {
+        self.width = width.max(1);
+        self
+    }
+
+    pub fn height(&mut self, height: usize) -> &mut Self {
+        self.height = height.max(2);
+        self
+    }
+
+    pub fn cycles(&mut self, cycles: Real) -> &mut Self {
+        self.cycles = cycles;
+        self
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_cycles(&self) -> Real {
+        self.cycles
+    }
+
+    pub fn finish<W>(&self, mut wave: W) -> Image
+    where
+        W: Wave,
+    {
+        let channels = usize::from(wave.channels().max(1));
+        let period = wave.sample_rate() as Real / wave.freq().abs().max(1.0);
+        let count = (period * self.cycles.max(0.0)).ceil() as usize + 1;
+        wave.reset_phase();
+        let samples: Vec<Real> =
+            wave.step_by(channels).take(count.max(2)).collect();
+
+        let mut image = Image::new(self.width, self.height, BACKGROUND);
+        image.horizontal_line(self.height / 2, AXIS);
+        let last = (samples.len() - 1) as Real;
+        let at = |x: usize| {
+            let position = x as Real * last / self.width as Real;
+            let index = position.floor() as usize;
+            let next =
+                samples.get(index + 1).copied().unwrap_or(samples[index]);
+            let frac = position - index as Real;
+            samples[index] * (1.0 - frac) + next * frac
+        };
+
+        let mut prev = sample_y(at(0), 0, self.height);
+        for x in 0 .. self.width {
+            let y = sample_y(at(x), 0, self.height);
+            image.vertical_line(x, prev, y, TRACE);
+            prev = y;
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WaveformBuilder, AXIS, BACKGROUND, ENVELOPE};
+    use crate::{
+        source::{SamplesBuffer, SourceBuilder},
+        wave::SineWaveBuilder,
+    };
+
+    #[test]
+    fn waveform_draws_constant_envelope() {
+        let source = SamplesBuffer::new(2, 48000, [0.5, -1.0].repeat(8));
+        let image = WaveformBuilder::default()
+            .width(4)
+            .channel_height(9)
+            .finish(source)
+            .unwrap();
+        assert_eq!((image.width(), image.height()), (4, 18));
+
+        for x in 0 .. 4 {
+            for y in 0 .. 18 {
+                let expected = match y {
+                    2 | 17 => ENVELOPE,
+                    4 | 13 => AXIS,
+                    _ => BACKGROUND,
+                };
+                assert_eq!(image.get(x, y), Some(expected), "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn waveform_rejects_infinite_sources() {
+        let wave = SineWaveBuilder::default().finish();
+        assert!(WaveformBuilder::default().finish(wave).is_none());
+    }
+}
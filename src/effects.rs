@@ -1,6 +1,32 @@
+mod shaper;
+mod dynamics;
+mod modulation;
+mod fade;
+mod level;
+
+pub use dynamics::{
+    Compressor,
+    CompressorBuilder,
+    Gate,
+    GateBuilder,
+    Limiter,
+    LimiterBuilder,
+};
+pub use fade::{
+    Crossfade,
+    FadeIn,
+    FadeInBuilder,
+    FadeOut,
+    FadeOutBuilder,
+    FadeShape,
+};
+pub use level::{DcBlocker, DcBlockerBuilder, Gain, Normalize};
+pub use modulation::{AmplitudeMod, AmplitudeModBuilder, RingMod};
+pub use shaper::{Curve, WaveShaper, WaveShaperBuilder};
+
 use crate::{
     num::{DurationExt, Natural, NaturalRatio, Real},
-    source::Source,
+    source::{frames_duration, Seekable, Source},
 };
 use std::time::Duration;
 
@@ -17,14 +43,15 @@ where
     final_vol: Real,
 }
 
-impl<S> Iterator for LinearFadeOut<S>
+impl<S> LinearFadeOut<S>
 where
     S: Source,
 {
-    type Item = Real;
+    pub(crate) fn into_inner(self) -> S {
+        self.inner
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let value = self.inner.next()? * self.curr_vol;
+    fn advance(&mut self) {
         self.channel = self.channel.saturating_sub(1);
         if self.channel == 0 {
             self.channel = self.channels();
@@ -32,6 +59,18 @@ where
                 self.curr_vol = (self.curr_vol - self.step).max(0.0);
             }
         }
+    }
+}
+
+impl<S> Iterator for LinearFadeOut<S>
+where
+    S: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.inner.next()? * self.curr_vol;
+        self.advance();
         Some(value)
     }
 }
@@ -55,6 +94,32 @@ where
     fn sample_rate(&self) -> u32 {
         self.inner.sample_rate()
     }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let len = self.inner.fill(buf);
+        for sample in &mut buf[.. len] {
+            *sample *= self.curr_vol;
+            self.advance();
+        }
+        len
+    }
+}
+
+impl<S> Seekable for LinearFadeOut<S>
+where
+    S: Seekable,
+{
+    fn seek(&mut self, frame: usize) {
+        self.inner.seek(frame);
+        self.channel = self.channels;
+        let frames = frame as Real;
+        let fading = ((1.0 - self.final_vol) / self.step).ceil().max(0.0);
+        self.curr_vol = if frames < fading {
+            1.0 - frames * self.step
+        } else {
+            (1.0 - fading * self.step).max(0.0)
+        };
+    }
 }
 
 pub struct LinearFadeOutBuilder {
@@ -92,6 +157,10 @@ impl LinearFadeOutBuilder {
         S: Source,
     {
         let len = source.len().unwrap_or(self.iterations);
+        let step = match len {
+            0 => 0.0,
+            len => (1.0 - self.final_vol) / len as Real,
+        };
         let channels = source.channels();
         LinearFadeOut {
             channels,
@@ -99,7 +168,7 @@ impl LinearFadeOutBuilder {
             inner: source,
             curr_vol: 1.0,
             final_vol: self.final_vol,
-            step: (1.0 - self.final_vol) / len as Real,
+            step,
         }
     }
 }
@@ -113,6 +182,7 @@ where
     channels: u16,
     channel: u16,
     rem_samples: usize,
+    total_samples: usize,
 }
 
 impl<S> Take<S>
@@ -121,7 +191,13 @@ where
 {
     pub(crate) fn new(inner: S, samples: usize) -> Self {
         let channels = inner.channels();
-        Self { channels, channel: channels, inner, rem_samples: samples }
+        Self {
+            channels,
+            channel: channels,
+            inner,
+            rem_samples: samples,
+            total_samples: samples,
+        }
     }
 
     pub fn max_duration(&self) -> Duration {
@@ -137,6 +213,10 @@ where
     pub fn max_len(&self) -> usize {
         self.rem_samples
     }
+
+    pub(crate) fn into_inner(self) -> S {
+        self.inner
+    }
 }
 
 impl<S> Iterator for Take<S>
@@ -190,4 +270,184 @@ where
     fn sample_rate(&self) -> u32 {
         self.inner.sample_rate()
     }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        if self.rem_samples == 0 {
+            return 0;
+        }
+
+        let channels = usize::from(self.channels.max(1));
+        let channel = match usize::from(self.channel) {
+            0 => channels,
+            channel => channel,
+        };
+        let allowed = channel + (self.rem_samples - 1) * channels - 1;
+        let wanted = buf.len().min(allowed);
+        let len = self.inner.fill(&mut buf[.. wanted]);
+
+        if len == allowed && len < buf.len() {
+            self.rem_samples = 0;
+        } else if len >= channel {
+            let rest = len - channel;
+            self.rem_samples -= 1 + rest / channels;
+            self.channel = (channels - rest % channels) as u16;
+        } else {
+            self.channel = (channel - len) as u16;
+        }
+        len
+    }
+}
+
+impl<S> Seekable for Take<S>
+where
+    S: Seekable,
+{
+    fn seek(&mut self, frame: usize) {
+        self.inner.seek(frame);
+        self.channel = self.channels;
+        self.rem_samples = self.total_samples.saturating_sub(frame);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Skip<S>
+where
+    S: Source,
+{
+    inner: S,
+    frames: usize,
+    rem_samples: usize,
+}
+
+impl<S> Skip<S>
+where
+    S: Source,
+{
+    pub(crate) fn new(inner: S, frames: usize) -> Self {
+        let channels = usize::from(inner.channels().max(1));
+        Self { rem_samples: frames.saturating_mul(channels), frames, inner }
+    }
+
+    fn skip(&mut self) {
+        let mut buf = [0.0; 256];
+        while self.rem_samples > 0 {
+            let wanted = self.rem_samples.min(buf.len());
+            let len = self.inner.fill(&mut buf[.. wanted]);
+            self.rem_samples -= len;
+            if len < wanted {
+                self.rem_samples = 0;
+            }
+        }
+    }
+}
+
+impl<S> Iterator for Skip<S>
+where
+    S: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.skip();
+        self.inner.next()
+    }
+}
+
+impl<S> Source for Skip<S>
+where
+    S: Source,
+{
+    fn len(&self) -> Option<usize> {
+        let channels = usize::from(self.channels().max(1));
+        let skipped = self.rem_samples / channels;
+        self.inner.len().map(|len| len.saturating_sub(skipped))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        let channels = usize::from(self.channels().max(1));
+        let skipped =
+            frames_duration(self.rem_samples / channels, self.sample_rate());
+        Some(self.inner.duration()?.saturating_sub(skipped))
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        self.skip();
+        self.inner.fill(buf)
+    }
+}
+
+impl<S> Seekable for Skip<S>
+where
+    S: Seekable,
+{
+    fn seek(&mut self, frame: usize) {
+        self.inner.seek(frame.saturating_add(self.frames));
+        self.rem_samples = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        num::Real,
+        source::{SamplesBuffer, Seekable, Source},
+    };
+
+    fn ramp(channels: u16, frames: usize) -> SamplesBuffer {
+        let samples = (0 .. frames * usize::from(channels))
+            .map(|sample| sample as Real)
+            .collect();
+        SamplesBuffer::new(channels, 1000, samples)
+    }
+
+    #[test]
+    fn take_seek_matches_skipped_playback() {
+        for &channels in &[1, 2] {
+            let take = ramp(channels, 20).take_samples(12);
+            let full = take.clone().collect::<Vec<_>>();
+            for frame in 0 .. 14 {
+                let mut sought = take.clone();
+                sought.seek(frame);
+                let start = frame * usize::from(channels);
+                let expected = full.get(start ..).unwrap_or(&[]);
+                assert_eq!(sought.collect::<Vec<_>>(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn take_fill_matches_next() {
+        for &channels in &[1, 2, 3] {
+            for &(frames, count) in &[(20, 12), (5, 12), (20, 1)] {
+                let take = ramp(channels, frames).take_samples(count);
+                let expected = take.clone().collect::<Vec<_>>();
+                for &size in &[1, 3, 5, 7] {
+                    let mut filled = take.clone();
+                    let mut buf = vec![0.0; size];
+                    let mut samples = Vec::new();
+                    loop {
+                        let len = filled.fill(&mut buf);
+                        if len == 0 {
+                            break;
+                        }
+                        samples.extend_from_slice(&buf[.. len]);
+                    }
+                    assert_eq!(
+                        samples, expected,
+                        "channels {}, frames {}, count {}, size {}",
+                        channels, frames, count, size
+                    );
+                    assert_eq!(filled.next(), None);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,790 @@
+use crate::{
+    num::{amplitude_to_db, db_to_amplitude, Real},
+    source::Source,
+};
+use std::{collections::VecDeque, time::Duration};
+
+fn time_coef(time: Duration, sample_rate: u32) -> Real {
+    let samples = time.as_secs_f64() as Real * sample_rate as Real;
+    if samples <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / samples).exp()
+    }
+}
+
+fn read_frame<S>(source: &mut S, frame: &mut Vec<Real>, channels: u16) -> bool
+where
+    S: Source + ?Sized,
+{
+    frame.clear();
+    for _ in 0 .. channels {
+        match source.next() {
+            Some(sample) => frame.push(sample),
+            None => break,
+        }
+    }
+    !frame.is_empty()
+}
+
+fn frame_peak(frame: &[Real]) -> Real {
+    frame.iter().fold(0.0, |peak, sample| sample.abs().max(peak))
+}
+
+#[derive(Debug, Clone)]
+struct Detector<K>
+where
+    K: Source,
+{
+    sidechain: Option<K>,
+    buf: Vec<Real>,
+}
+
+impl<K> Detector<K>
+where
+    K: Source,
+{
+    fn new(sidechain: Option<K>) -> Self {
+        Self { sidechain, buf: Vec::new() }
+    }
+
+    fn level(&mut self, frame: &[Real]) -> Real {
+        match &mut self.sidechain {
+            Some(sidechain) => {
+                let channels = sidechain.channels();
+                if read_frame(sidechain, &mut self.buf, channels) {
+                    frame_peak(&self.buf)
+                } else {
+                    0.0
+                }
+            },
+            None => frame_peak(frame),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Compressor<S, K = S>
+where
+    S: Source,
+    K: Source,
+{
+    inner: S,
+    detector: Detector<K>,
+    threshold: Real,
+    ratio: Real,
+    knee: Real,
+    makeup: Real,
+    attack: Real,
+    release: Real,
+    reduction: Real,
+    frame: Vec<Real>,
+    cursor: usize,
+}
+
+impl<S, K> Compressor<S, K>
+where
+    S: Source,
+    K: Source,
+{
+    fn curve(&self, level: Real) -> Real {
+        let over = level - self.threshold;
+        if 2.0 * over < -self.knee {
+            level
+        } else if self.knee > 0.0 && 2.0 * over.abs() <= self.knee {
+            let knee_over = over + self.knee / 2.0;
+            level
+                + (1.0 / self.ratio - 1.0) * knee_over * knee_over
+                    / (2.0 * self.knee)
+        } else {
+            self.threshold + over / self.ratio
+        }
+    }
+
+    fn process_frame(&mut self) -> bool {
+        let channels = self.inner.channels();
+        if !read_frame(&mut self.inner, &mut self.frame, channels) {
+            return false;
+        }
+        self.cursor = 0;
+
+        let level = amplitude_to_db(self.detector.level(&self.frame));
+        let target = if level.is_finite() {
+            (self.curve(level) - level).min(0.0)
+        } else {
+            0.0
+        };
+        let coef =
+            if target < self.reduction { self.attack } else { self.release };
+        self.reduction = coef * self.reduction + (1.0 - coef) * target;
+
+        let gain = db_to_amplitude(self.reduction) * self.makeup;
+        for sample in &mut self.frame {
+            *sample *= gain;
+        }
+        true
+    }
+
+    pub fn reduction(&self) -> Real {
+        self.reduction
+    }
+}
+
+impl<S, K> Iterator for Compressor<S, K>
+where
+    S: Source,
+    K: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cursor >= self.frame.len() && !self.process_frame() {
+            return None;
+        }
+        let sample = self.frame[self.cursor];
+        self.cursor += 1;
+        Some(sample)
+    }
+}
+
+impl<S, K> Source for Compressor<S, K>
+where
+    S: Source,
+    K: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.inner.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompressorBuilder {
+    threshold: Real,
+    ratio: Real,
+    attack: Duration,
+    release: Duration,
+    knee: Real,
+    makeup: Real,
+}
+
+impl Default for CompressorBuilder {
+    fn default() -> Self {
+        Self {
+            threshold: -18.0,
+            ratio: 4.0,
+            attack: Duration::from_millis(10),
+            release: Duration::from_millis(100),
+            knee: 6.0,
+            makeup: 0.0,
+        }
+    }
+}
+
+impl CompressorBuilder {
+    pub fn threshold(&mut self, threshold: Real) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn ratio(&mut self, ratio: Real) -> &mut Self {
+        self.ratio = ratio;
+        self
+    }
+
+    pub fn attack(&mut self, attack: Duration) -> &mut Self {
+        self.attack = attack;
+        self
+    }
+
+    pub fn release(&mut self, release: Duration) -> &mut Self {
+        self.release = release;
+        self
+    }
+
+    pub fn knee(&mut self, knee: Real) -> &mut Self {
+        self.knee = knee;
+        self
+    }
+
+    pub fn makeup(&mut self, makeup: Real) -> &mut Self {
+        self.makeup = makeup;
+        self
+    }
+
+    pub fn get_threshold(&self) -> Real {
+        self.threshold
+    }
+
+    pub fn get_ratio(&self) -> Real {
+        self.ratio
+    }
+
+    pub fn get_attack(&self) -> Duration {
+        self.attack
+    }
+
+    pub fn get_release(&self) -> Duration {
+        self.release
+    }
+
+    pub fn get_knee(&self) -> Real {
+        self.knee
+    }
+
+    pub fn get_makeup(&self) -> Real {
+        self.makeup
+    }
+
+    pub fn finish<S>(&self, source: S) -> Compressor<S>
+    where
+        S: Source,
+    {
+        self.make(source, None)
+    }
+
+    pub fn finish_sidechain<S, K>(&self, source: S, key: K) -> Compressor<S, K>
+    where
+        S: Source,
+        K: Source,
+    {
+        self.make(source, Some(key))
+    }
+
+    fn make<S, K>(&self, source: S, sidechain: Option<K>) -> Compressor<S, K>
+    where
+        S: Source,
+        K: Source,
+    {
+        let rate = source.sample_rate();
+        Compressor {
+            inner: source,
+            detector: Detector::new(sidechain),
+            threshold: self.threshold,
+            ratio: self.ratio.max(1.0),
+            knee: self.knee.max(0.0),
+            makeup: db_to_amplitude(self.makeup),
+            attack: time_coef(self.attack, rate),
+            release: time_coef(self.release, rate),
+            reduction: 0.0,
+            frame: Vec::new(),
+            cursor: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Limiter<S>
+where
+    S: Source,
+{
+    inner: S,
+    ceiling: Real,
+    lookahead: usize,
+    release: Real,
+    gain: Real,
+    delay: VecDeque<Real>,
+    lengths: VecDeque<usize>,
+    peaks: VecDeque<(usize, Real)>,
+    holds: VecDeque<Real>,
+    hold_sum: Real,
+    pulled: usize,
+    emitted: usize,
+    frame: Vec<Real>,
+    cursor: usize,
+    primed: bool,
+}
+
+impl<S> Limiter<S>
+where
+    S: Source,
+{
+    fn pull_frame(&mut self) -> bool {
+        let channels = self.inner.channels();
+        if !read_frame(&mut self.inner, &mut self.frame, channels) {
+            return false;
+        }
+        self.delay.extend(self.frame.iter().copied());
+        self.lengths.push_back(self.frame.len());
+
+        let peak = frame_peak(&self.frame);
+        while matches!(self.peaks.back(), Some(&(_, back)) if back <= peak) {
+            self.peaks.pop_back();
+        }
+        self.peaks.push_back((self.pulled, peak));
+        self.pulled += 1;
+        true
+    }
+
+    fn process_frame(&mut self) -> bool {
+        if !self.primed {
+            self.primed = true;
+            for _ in 0 .. self.lookahead {
+                if !self.pull_frame() {
+                    break;
+                }
+            }
+        }
+        self.pull_frame();
+
+        let len = match self.lengths.pop_front() {
+            Some(len) => len,
+            None => return false,
+        };
+        while matches!(
+            self.peaks.front(),
+            Some(&(index, _)) if index < self.emitted
+        ) {
+            self.peaks.pop_front();
+        }
+        let peak = self.peaks.front().map_or(0.0, |&(_, peak)| peak);
+        self.emitted += 1;
+
+        let target =
+            if peak > self.ceiling { self.ceiling / peak } else { 1.0 };
+        self.holds.push_back(target);
+        self.hold_sum += target;
+        if self.holds.len() > self.lookahead + 1 {
+            self.hold_sum -= self.holds.pop_front().unwrap_or(0.0);
+        }
+        self.frame.clear();
+        self.frame.extend(self.delay.drain(.. len));
+        let own = frame_peak(&self.frame);
+        let mut ramp = self.hold_sum / self.holds.len() as Real;
+        if own > self.ceiling {
+            ramp = ramp.min(self.ceiling / own);
+        }
+        if ramp < self.gain {
+            self.gain = ramp;
+        } else {
+            self.gain = ramp + self.release * (self.gain - ramp);
+        }
+
+        for sample in &mut self.frame {
+            *sample *= self.gain;
+        }
+        self.cursor = 0;
+        true
+    }
+
+    pub fn gain(&self) -> Real {
+        self.gain
+    }
+}
+
+impl<S> Iterator for Limiter<S>
+where
+    S: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cursor >= self.frame.len() && !self.process_frame() {
+            return None;
+        }
+        let sample = self.frame[self.cursor];
+        self.cursor += 1;
+        Some(sample)
+    }
+}
+
+impl<S> Source for Limiter<S>
+where
+    S: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.inner.len().map(|len| len + self.lengths.len())
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LimiterBuilder {
+    ceiling: Real,
+    lookahead: Duration,
+    release: Duration,
+}
+
+impl Default for LimiterBuilder {
+    fn default() -> Self {
+        Self {
+            ceiling: -0.3,
+            lookahead: Duration::from_millis(5),
+            release: Duration::from_millis(50),
+        }
+    }
+}
+
+impl LimiterBuilder {
+    pub fn ceiling(&mut self, ceiling: Real) -> &mut Self {
+        self.ceiling = ceiling;
+        self
+    }
+
+    pub fn lookahead(&mut self, lookahead: Duration) -> &mut Self {
+        self.lookahead = lookahead;
+        self
+    }
+
+    pub fn release(&mut self, release: Duration) -> &mut Self {
+        self.release = release;
+        self
+    }
+
+    pub fn get_ceiling(&self) -> Real {
+        self.ceiling
+    }
+
+    pub fn get_lookahead(&self) -> Duration {
+        self.lookahead
+    }
+
+    pub fn get_release(&self) -> Duration {
+        self.release
+    }
+
+    pub fn finish<S>(&self, source: S) -> Limiter<S>
+    where
+        S: Source,
+    {
+        let rate = source.sample_rate();
+        let lookahead = self.lookahead.as_secs_f64() * rate as f64;
+        let lookahead = lookahead.round() as usize;
+        Limiter {
+            inner: source,
+            ceiling: db_to_amplitude(self.ceiling),
+            lookahead,
+            release: time_coef(self.release, rate),
+            gain: 1.0,
+            delay: VecDeque::new(),
+            lengths: VecDeque::new(),
+            peaks: VecDeque::new(),
+            holds: vec![1.0; lookahead].into(),
+            hold_sum: lookahead as Real,
+            pulled: 0,
+            emitted: 0,
+            frame: Vec::new(),
+            cursor: 0,
+            primed: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Gate<S, K = S>
+where
+    S: Source,
+    K: Source,
+{
+    inner: S,
+    detector: Detector<K>,
+    threshold: Real,
+    floor: Real,
+    attack: Real,
+    release: Real,
+    hold: usize,
+    hold_remaining: usize,
+    gain: Real,
+    frame: Vec<Real>,
+    cursor: usize,
+}
+
+impl<S, K> Gate<S, K>
+where
+    S: Source,
+    K: Source,
+{
+    fn process_frame(&mut self) -> bool {
+        let channels = self.inner.channels();
+        if !read_frame(&mut self.inner, &mut self.frame, channels) {
+            return false;
+        }
+        self.cursor = 0;
+
+        let level = self.detector.level(&self.frame);
+        let target = if level >= self.threshold {
+            self.hold_remaining = self.hold;
+            1.0
+        } else if self.hold_remaining > 0 {
+            self.hold_remaining -= 1;
+            1.0
+        } else {
+            self.floor
+        };
+        let coef = if target > self.gain { self.attack } else { self.release };
+        self.gain = target + coef * (self.gain - target);
+
+        for sample in &mut self.frame {
+            *sample *= self.gain;
+        }
+        true
+    }
+
+    pub fn gain(&self) -> Real {
+        self.gain
+    }
+}
+
+impl<S, K> Iterator for Gate<S, K>
+where
+    S: Source,
+    K: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cursor >= self.frame.len() && !self.process_frame() {
+            return None;
+        }
+        let sample = self.frame[self.cursor];
+        self.cursor += 1;
+        Some(sample)
+    }
+}
+
+impl<S, K> Source for Gate<S, K>
+where
+    S: Source,
+    K: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.inner.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GateBuilder {
+    threshold: Real,
+    floor: Real,
+    attack: Duration,
+    hold: Duration,
+    release: Duration,
+}
+
+impl Default for GateBuilder {
+    fn default() -> Self {
+        Self {
+            threshold: -50.0,
+            floor: -80.0,
+            attack: Duration::from_millis(1),
+            hold: Duration::from_millis(50),
+            release: Duration::from_millis(100),
+        }
+    }
+}
+
+impl GateBuilder {
+    pub fn threshold(&mut self, threshold: Real) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn floor(&mut self, floor: Real) -> &mut Self {
+        self.floor = floor;
+        self
+    }
+
+    pub fn attack(&mut self, attack: Duration) -> &mut Self {
+        self.attack = attack;
+        self
+    }
+
+    pub fn hold(&mut self, hold: Duration) -> &mut Self {
+        self.hold = hold;
+        self
+    }
+
+    pub fn release(&mut self, release: Duration) -> &mut Self {
+        self.release = release;
+        self
+    }
+
+    pub fn get_threshold(&self) -> Real {
+        self.threshold
+    }
+
+    pub fn get_floor(&self) -> Real {
+        self.floor
+    }
+
+    pub fn get_attack(&self) -> Duration {
+        self.attack
+    }
+
+    pub fn get_hold(&self) -> Duration {
+        self.hold
+    }
+
+    pub fn get_release(&self) -> Duration {
+        self.release
+    }
+
+    pub fn finish<S>(&self, source: S) -> Gate<S>
+    where
+        S: Source,
+    {
+        self.make(source, None)
+    }
+
+    pub fn finish_sidechain<S, K>(&self, source: S, key: K) -> Gate<S, K>
+    where
+        S: Source,
+        K: Source,
+    {
+        self.make(source, Some(key))
+    }
+
+    fn make<S, K>(&self, source: S, sidechain: Option<K>) -> Gate<S, K>
+    where
+        S: Source,
+        K: Source,
+    {
+        let rate = source.sample_rate();
+        let hold = self.hold.as_secs_f64() * rate as f64;
+        Gate {
+            inner: source,
+            detector: Detector::new(sidechain),
+            threshold: db_to_amplitude(self.threshold),
+            floor: db_to_amplitude(self.floor),
+            attack: time_coef(self.attack, rate),
+            release: time_coef(self.release, rate),
+            hold: hold.round() as usize,
+            hold_remaining: 0,
+            gain: 0.0,
+            frame: Vec::new(),
+            cursor: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompressorBuilder, GateBuilder, LimiterBuilder};
+    use crate::{
+        num::{db_to_amplitude, Real},
+        source::SamplesBuffer,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn hard_knee_at_threshold_is_finite() {
+        let source = SamplesBuffer::new(1, 48000, vec![1.0; 64]);
+        let compressor = CompressorBuilder::default()
+            .threshold(0.0)
+            .knee(0.0)
+            .finish(source);
+        let samples: Vec<Real> = compressor.collect();
+        assert_eq!(samples.len(), 64);
+        assert!(samples.iter().all(|sample| sample.is_finite()));
+    }
+
+    #[test]
+    fn limiter_ramps_below_ceiling() {
+        let mut samples = vec![0.5; 2000];
+        samples[1000] = 2.0;
+        let source = SamplesBuffer::new(1, 48000, samples.clone());
+        let output: Vec<Real> = LimiterBuilder::default()
+            .ceiling(0.0)
+            .lookahead(Duration::from_millis(1))
+            .finish(source)
+            .collect();
+
+        assert_eq!(output.len(), samples.len());
+        let ceiling = db_to_amplitude(0.0);
+        assert!(output.iter().all(|sample| sample.abs() <= ceiling + 1e-9));
+
+        let gains: Vec<Real> =
+            output.iter().zip(&samples).map(|(out, inp)| out / inp).collect();
+        let max_step = gains
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0, Real::max);
+        assert!(max_step < 0.05, "gain step {}", max_step);
+    }
+
+    #[test]
+    fn compressor_follows_borrowed_sidechain() {
+        let mut samples = vec![1.0; 4800];
+        samples.extend(vec![0.0; 4800]);
+        let mut key = SamplesBuffer::new(1, 48000, samples);
+        let source = SamplesBuffer::new(1, 48000, vec![0.1; 9600]);
+        let output: Vec<Real> = CompressorBuilder::default()
+            .threshold(-20.0)
+            .ratio(10.0)
+            .attack(Duration::from_millis(1))
+            .release(Duration::from_millis(1))
+            .knee(0.0)
+            .finish_sidechain(source, &mut key)
+            .collect();
+
+        assert_eq!(output.len(), 9600);
+        assert_eq!(key.next(), None);
+        assert!(output[4700] < 0.02, "{}", output[4700]);
+        assert!((output[9599] - 0.1).abs() < 1e-3, "{}", output[9599]);
+    }
+
+    #[test]
+    fn gate_follows_borrowed_sidechain() {
+        let mut samples = vec![0.0; 4800];
+        samples.extend(vec![1.0; 4800]);
+        let mut key = SamplesBuffer::new(1, 48000, samples);
+        let source = SamplesBuffer::new(1, 48000, vec![0.5; 9600]);
+        let output: Vec<Real> = GateBuilder::default()
+            .attack(Duration::from_millis(1))
+            .finish_sidechain(source, &mut key)
+            .collect();
+
+        assert_eq!(key.next(), None);
+        assert!(output[.. 4800].iter().all(|&sample| sample < 1e-3));
+        assert!((output[9599] - 0.5).abs() < 1e-3, "{}", output[9599]);
+    }
+
+    #[test]
+    fn cloned_dynamics_continue_identically() {
+        let source = || SamplesBuffer::new(1, 48000, [0.9, -0.1].repeat(1200));
+        let mut compressor =
+            CompressorBuilder::default().finish_sidechain(source(), source());
+        let mut gate = GateBuilder::default().finish(source());
+        compressor.by_ref().take(1000).for_each(drop);
+        gate.by_ref().take(1000).for_each(drop);
+
+        let cloned: Vec<Real> = compressor.clone().collect();
+        assert_eq!(cloned, compressor.collect::<Vec<_>>());
+        let cloned: Vec<Real> = gate.clone().collect();
+        assert_eq!(cloned, gate.collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,542 @@
+use crate::{
+    num::{
+        real::consts::{FRAC_PI_2, PI},
+        Real,
+    },
+    source::{duration_frames, frames_duration, Seekable, Source},
+};
+use std::{collections::VecDeque, time::Duration};
+
+#[derive(Debug, Clone)]
+pub struct Crossfade<A, B>
+where
+    A: Source,
+    B: Source,
+{
+    first: A,
+    second: B,
+    channels: usize,
+    overlap: usize,
+    queue: VecDeque<Real>,
+    fade_len: Option<usize>,
+}
+
+impl<A, B> Crossfade<A, B>
+where
+    A: Source,
+    B: Source,
+{
+    pub(crate) fn new(first: A, second: B, duration: Duration) -> Self {
+        let channels = usize::from(first.channels().max(1));
+        let frames = duration_frames(duration, first.sample_rate());
+        let overlap = frames.saturating_mul(channels);
+        Self {
+            first,
+            second,
+            channels,
+            overlap,
+            queue: VecDeque::with_capacity(overlap.saturating_add(1)),
+            fade_len: None,
+        }
+    }
+
+    fn queued_frames(&self) -> usize {
+        self.queue.len() / self.channels
+    }
+}
+
+impl<A, B> Iterator for Crossfade<A, B>
+where
+    A: Source,
+    B: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fade_len.is_none() {
+            while self.queue.len() <= self.overlap {
+                match self.first.next() {
+                    Some(sample) => self.queue.push_back(sample),
+                    None => {
+                        self.fade_len = Some(self.queue.len());
+                        break;
+                    },
+                }
+            }
+            if self.fade_len.is_none() {
+                return self.queue.pop_front();
+            }
+        }
+
+        let fade_len = self.fade_len.unwrap_or(0);
+        let first = match self.queue.pop_front() {
+            Some(sample) => sample,
+            None => return self.second.next(),
+        };
+        let frame = (fade_len - self.queue.len() - 1) / self.channels;
+        let frames = (fade_len / self.channels).max(1);
+        let angle = (frame as Real + 0.5) / frames as Real * FRAC_PI_2;
+        let second = self.second.next().unwrap_or(0.0);
+        Some(first * angle.cos() + second * angle.sin())
+    }
+}
+
+impl<A, B> Source for Crossfade<A, B>
+where
+    A: Source,
+    B: Source,
+{
+    fn len(&self) -> Option<usize> {
+        let second = self.second.len()?;
+        let first = match self.fade_len {
+            Some(_) => self.queued_frames(),
+            None => self.first.len()? + self.queued_frames(),
+        };
+        let fade = match self.fade_len {
+            Some(_) => first,
+            None => first.min(self.overlap / self.channels),
+        };
+        Some(first + second.saturating_sub(fade))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(frames_duration(self.len()?, self.sample_rate()))
+    }
+
+    fn channels(&self) -> u16 {
+        self.first.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.first.sample_rate()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FadeShape {
+    Linear,
+    Exponential,
+    Logarithmic,
+    SCurve,
+    EqualPower,
+}
+
+impl FadeShape {
+    pub fn gain(self, progress: Real) -> Real {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            FadeShape::Linear => progress,
+            FadeShape::Exponential => {
+                (Real::exp(4.0 * progress) - 1.0) / (Real::exp(4.0) - 1.0)
+            },
+            FadeShape::Logarithmic => Real::log10(1.0 + 9.0 * progress),
+            FadeShape::SCurve => (1.0 - (PI * progress).cos()) / 2.0,
+            FadeShape::EqualPower => (FRAC_PI_2 * progress).sin(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FadeIn<S>
+where
+    S: Source,
+{
+    inner: S,
+    shape: FadeShape,
+    frames: usize,
+    position: usize,
+    channels: u16,
+    channel: u16,
+}
+
+impl<S> FadeIn<S>
+where
+    S: Source,
+{
+    fn advance(&mut self) -> Real {
+        let gain = if self.position < self.frames {
+            self.shape.gain(self.position as Real / self.frames as Real)
+        } else {
+            1.0
+        };
+        self.channel += 1;
+        if self.channel >= self.channels {
+            self.channel = 0;
+            self.position = self.position.saturating_add(1);
+        }
+        gain
+    }
+}
+
+impl<S> Iterator for FadeIn<S>
+where
+    S: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.inner.next()?;
+        Some(sample * self.advance())
+    }
+}
+
+impl<S> Source for FadeIn<S>
+where
+    S: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.inner.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let len = self.inner.fill(buf);
+        for sample in &mut buf[.. len] {
+            *sample *= self.advance();
+        }
+        len
+    }
+}
+
+impl<S> Seekable for FadeIn<S>
+where
+    S: Seekable,
+{
+    fn seek(&mut self, frame: usize) {
+        self.inner.seek(frame);
+        self.position = frame;
+        self.channel = 0;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FadeInBuilder {
+    duration: Duration,
+    shape: FadeShape,
+}
+
+impl Default for FadeInBuilder {
+    fn default() -> Self {
+        Self { duration: Duration::from_secs(1), shape: FadeShape::Linear }
+    }
+}
+
+impl FadeInBuilder {
+    pub fn duration(&mut self, duration: Duration) -> &mut Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn shape(&mut self, shape: FadeShape) -> &mut Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn get_shape(&self) -> FadeShape {
+        self.shape
+    }
+
+    pub fn finish<S>(&self, source: S) -> FadeIn<S>
+    where
+        S: Source,
+    {
+        FadeIn {
+            frames: duration_frames(self.duration, source.sample_rate()),
+            channels: source.channels().max(1),
+            inner: source,
+            shape: self.shape,
+            position: 0,
+            channel: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FadeOut<S>
+where
+    S: Source,
+{
+    inner: S,
+    shape: FadeShape,
+    start: usize,
+    frames: usize,
+    position: usize,
+    channels: u16,
+    channel: u16,
+}
+
+impl<S> FadeOut<S>
+where
+    S: Source,
+{
+    fn end(&self) -> usize {
+        self.start.saturating_add(self.frames)
+    }
+
+    fn advance(&mut self) -> Real {
+        let gain = if self.position < self.start {
+            1.0
+        } else {
+            let faded = self.position - self.start + 1;
+            self.shape.gain(1.0 - faded as Real / self.frames as Real)
+        };
+        self.channel += 1;
+        if self.channel >= self.channels {
+            self.channel = 0;
+            self.position += 1;
+        }
+        gain
+    }
+}
+
+impl<S> Iterator for FadeOut<S>
+where
+    S: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.end() {
+            return None;
+        }
+        let sample = self.inner.next()?;
+        Some(sample * self.advance())
+    }
+}
+
+impl<S> Source for FadeOut<S>
+where
+    S: Source,
+{
+    fn len(&self) -> Option<usize> {
+        let len = self.end().saturating_sub(self.position);
+        Some(self.inner.len().map_or(len, |inner| inner.min(len)))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(frames_duration(self.len()?, self.sample_rate()))
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let channels = usize::from(self.channels);
+        let frames = self.end().saturating_sub(self.position);
+        let wanted = frames
+            .saturating_mul(channels)
+            .saturating_sub(usize::from(self.channel))
+            .min(buf.len());
+        let len = self.inner.fill(&mut buf[.. wanted]);
+        for sample in &mut buf[.. len] {
+            *sample *= self.advance();
+        }
+        len
+    }
+}
+
+impl<S> Seekable for FadeOut<S>
+where
+    S: Seekable,
+{
+    fn seek(&mut self, frame: usize) {
+        self.inner.seek(frame);
+        self.position = frame;
+        self.channel = 0;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FadeOutBuilder {
+    duration: Duration,
+    shape: FadeShape,
+}
+
+impl Default for FadeOutBuilder {
+    fn default() -> Self {
+        Self { duration: Duration::from_secs(1), shape: FadeShape::Linear }
+    }
+}
+
+impl FadeOutBuilder {
+    pub fn duration(&mut self, duration: Duration) -> &mut Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn shape(&mut self, shape: FadeShape) -> &mut Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn get_shape(&self) -> FadeShape {
+        self.shape
+    }
+
+    pub fn finish<S>(&self, source: S) -> FadeOut<S>
+    where
+        S: Source,
+    {
+        let frames = duration_frames(self.duration, source.sample_rate());
+        let start = source.len().map_or(0, |len| len.saturating_sub(frames));
+        FadeOut {
+            frames: frames.max(1),
+            start,
+            channels: source.channels().max(1),
+            inner: source,
+            shape: self.shape,
+            position: 0,
+            channel: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FadeInBuilder, FadeOutBuilder, FadeShape};
+    use crate::{
+        num::{real::consts::FRAC_1_SQRT_2, Real},
+        source::{SamplesBuffer, Seekable, Source},
+    };
+    use std::time::Duration;
+
+    fn stereo(frames: usize, left: Real, right: Real) -> SamplesBuffer {
+        SamplesBuffer::new(2, 1000, [left, right].repeat(frames))
+    }
+
+    #[test]
+    fn crossfade_gains_are_equal_power() {
+        let fade = Duration::from_millis(5);
+        let first: Vec<Real> = stereo(8, 1.0, 1.0)
+            .crossfade(stereo(8, 0.0, 0.0), fade)
+            .step_by(2)
+            .collect();
+        let second: Vec<Real> = stereo(8, 0.0, 0.0)
+            .crossfade(stereo(8, 1.0, 1.0), fade)
+            .step_by(2)
+            .collect();
+        assert_eq!(first.len(), 11);
+        assert_eq!(second.len(), 11);
+
+        assert_eq!(&first[.. 3], &[1.0; 3]);
+        assert_eq!(&second[.. 3], &[0.0; 3]);
+        assert_eq!(&first[8 ..], &[0.0; 3]);
+        assert_eq!(&second[8 ..], &[1.0; 3]);
+        assert!((first[5] - FRAC_1_SQRT_2).abs() < 1e-6, "{}", first[5]);
+        assert!((second[5] - FRAC_1_SQRT_2).abs() < 1e-6, "{}", second[5]);
+        for frame in 3 .. 8 {
+            let power = first[frame].powi(2) + second[frame].powi(2);
+            assert!((power - 1.0).abs() < 1e-6, "{}: {}", frame, power);
+        }
+        for frame in 3 .. 7 {
+            assert!(first[frame] > first[frame + 1]);
+            assert!(second[frame] < second[frame + 1]);
+        }
+    }
+
+    #[test]
+    fn crossfade_keeps_channels_aligned() {
+        let samples: Vec<Real> = stereo(8, 1.0, 2.0)
+            .crossfade(stereo(8, 10.0, 20.0), Duration::from_millis(5))
+            .collect();
+        assert_eq!(samples.len(), 22);
+        for frame in samples.chunks(2) {
+            assert!((frame[1] - 2.0 * frame[0]).abs() < 1e-5, "{:?}", frame);
+        }
+    }
+
+    #[test]
+    fn fade_shapes_follow_their_curves() {
+        let e2 = (2.0 as Real).exp();
+        let shapes = [
+            (FadeShape::Linear, 0.5),
+            (FadeShape::Exponential, 1.0 / (e2 + 1.0)),
+            (FadeShape::Logarithmic, (5.5 as Real).log10()),
+            (FadeShape::SCurve, 0.5),
+            (FadeShape::EqualPower, FRAC_1_SQRT_2),
+        ];
+        for &(shape, midpoint) in &shapes {
+            assert_eq!(shape.gain(0.0), 0.0, "{:?}", shape);
+            assert!((shape.gain(1.0) - 1.0).abs() < 1e-6, "{:?}", shape);
+            assert_eq!(shape.gain(-1.0), shape.gain(0.0), "{:?}", shape);
+            assert_eq!(shape.gain(2.0), shape.gain(1.0), "{:?}", shape);
+            let gain = shape.gain(0.5);
+            assert!((gain - midpoint).abs() < 1e-6, "{:?}: {}", shape, gain);
+            for step in 0 .. 10 {
+                let progress = step as Real / 10.0;
+                assert!(shape.gain(progress) < shape.gain(progress + 0.1));
+            }
+        }
+        assert!((FadeShape::SCurve.gain(0.25) - 0.1464466).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fades_keep_channels_aligned() {
+        let fade = Duration::from_millis(4);
+        let mut fade_in =
+            FadeInBuilder::default().duration(fade).finish(stereo(6, 1.0, 2.0));
+        let mut samples = vec![0.0; 5];
+        assert_eq!(fade_in.fill(&mut samples), 5);
+        samples.extend(fade_in);
+        assert_eq!(
+            samples,
+            [0.0, 0.0, 0.25, 0.5, 0.5, 1.0, 0.75, 1.5, 1.0, 2.0, 1.0, 2.0]
+        );
+
+        let mut fade_out = FadeOutBuilder::default()
+            .duration(fade)
+            .finish(stereo(6, 1.0, 2.0));
+        let mut samples = vec![0.0; 7];
+        assert_eq!(fade_out.fill(&mut samples), 7);
+        samples.extend(fade_out);
+        assert_eq!(
+            samples,
+            [1.0, 2.0, 1.0, 2.0, 0.75, 1.5, 0.5, 1.0, 0.25, 0.5, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn fade_out_seek_past_end() {
+        let source = SamplesBuffer::new(2, 1000, vec![0.5; 200]);
+        let mut fade = FadeOutBuilder::default()
+            .duration(Duration::from_millis(20))
+            .finish(source);
+        assert_eq!(fade.len(), Some(100));
+
+        fade.seek(150);
+        assert_eq!(fade.len(), Some(0));
+        assert_eq!(fade.duration(), Some(Duration::from_secs(0)));
+        let mut buf = [1.0; 8];
+        assert_eq!(fade.fill(&mut buf), 0);
+        assert_eq!(fade.next(), None);
+
+        fade.seek(90);
+        assert_eq!(fade.len(), Some(10));
+        assert_eq!(fade.fill(&mut buf), 8);
+        assert!(buf.iter().all(|&sample| sample > 0.0 && sample < 0.5));
+    }
+}
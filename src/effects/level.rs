@@ -0,0 +1,286 @@
+use crate::{
+    num::{db_to_amplitude, real::consts::PI, Real},
+    source::{SamplesBuffer, Seekable, Source},
+};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct Gain<S>
+where
+    S: Source,
+{
+    inner: S,
+    amplitude: Real,
+}
+
+impl<S> Gain<S>
+where
+    S: Source,
+{
+    pub(crate) fn new(inner: S, amplitude: Real) -> Self {
+        Self { inner, amplitude }
+    }
+
+    pub fn amplitude(&self) -> Real {
+        self.amplitude
+    }
+}
+
+impl<S> Iterator for Gain<S>
+where
+    S: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|sample| sample * self.amplitude)
+    }
+}
+
+impl<S> Source for Gain<S>
+where
+    S: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.inner.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let len = self.inner.fill(buf);
+        for sample in &mut buf[.. len] {
+            *sample *= self.amplitude;
+        }
+        len
+    }
+}
+
+impl<S> Seekable for Gain<S>
+where
+    S: Seekable,
+{
+    fn seek(&mut self, frame: usize) {
+        self.inner.seek(frame);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Normalize {
+    Peak(Real),
+    Rms(Real),
+}
+
+impl Normalize {
+    pub(crate) fn apply(self, buffer: SamplesBuffer) -> Gain<SamplesBuffer> {
+        let samples = buffer.samples();
+        let (level, target) = match self {
+            Normalize::Peak(target) => {
+                let peak = samples
+                    .iter()
+                    .fold(0.0, |peak: Real, sample| sample.abs().max(peak));
+                (peak, target)
+            },
+            Normalize::Rms(target) => {
+                let sum: Real =
+                    samples.iter().map(|sample| sample * sample).sum();
+                let len = samples.len().max(1) as Real;
+                ((sum / len).sqrt(), target)
+            },
+        };
+        let amplitude =
+            if level > 0.0 { db_to_amplitude(target) / level } else { 1.0 };
+        Gain::new(buffer, amplitude)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DcBlocker<S>
+where
+    S: Source,
+{
+    inner: S,
+    coef: Real,
+    inputs: Vec<Real>,
+    outputs: Vec<Real>,
+    channel: usize,
+}
+
+impl<S> DcBlocker<S>
+where
+    S: Source,
+{
+    fn filter(&mut self, input: Real) -> Real {
+        let channel = self.channel;
+        let output =
+            input - self.inputs[channel] + self.coef * self.outputs[channel];
+        self.inputs[channel] = input;
+        self.outputs[channel] = output;
+        self.channel = (channel + 1) % self.inputs.len();
+        output
+    }
+}
+
+impl<S> Iterator for DcBlocker<S>
+where
+    S: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.inner.next()?;
+        Some(self.filter(input))
+    }
+}
+
+impl<S> Source for DcBlocker<S>
+where
+    S: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.inner.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let len = self.inner.fill(buf);
+        for sample in &mut buf[.. len] {
+            *sample = self.filter(*sample);
+        }
+        len
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DcBlockerBuilder {
+    cutoff: Real,
+}
+
+impl Default for DcBlockerBuilder {
+    fn default() -> Self {
+        Self { cutoff: 10.0 }
+    }
+}
+
+impl DcBlockerBuilder {
+    pub fn cutoff(&mut self, cutoff: Real) -> &mut Self {
+        self.cutoff = cutoff;
+        self
+    }
+
+    pub fn get_cutoff(&self) -> Real {
+        self.cutoff
+    }
+
+    pub fn finish<S>(&self, source: S) -> DcBlocker<S>
+    where
+        S: Source,
+    {
+        let channels = usize::from(source.channels().max(1));
+        let sample_rate = source.sample_rate().max(1) as Real;
+        DcBlocker {
+            coef: (-2.0 * PI * self.cutoff.max(0.0) / sample_rate).exp(),
+            inner: source,
+            inputs: vec![0.0; channels],
+            outputs: vec![0.0; channels],
+            channel: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DcBlockerBuilder, Normalize};
+    use crate::{
+        num::{amplitude_to_db, real::consts::PI, Real},
+        source::{SamplesBuffer, Source, SourceBuilder},
+        wave::{SawWaveBuilder, SineWaveBuilder, WaveBuilder},
+    };
+
+    fn peak(samples: &[Real]) -> Real {
+        samples.iter().fold(0.0, |peak: Real, sample| sample.abs().max(peak))
+    }
+
+    fn rms(samples: &[Real]) -> Real {
+        let sum: Real = samples.iter().map(|sample| sample * sample).sum();
+        (sum / samples.len() as Real).sqrt()
+    }
+
+    #[test]
+    fn gain_scales_by_decibels() {
+        let source = SamplesBuffer::new(1, 8000, vec![0.5, -0.25, 0.1]);
+        let gain = source.clone().gain(-20.0);
+        assert!((gain.amplitude() - 0.1).abs() < 1e-12);
+
+        let mut buf = [0.0; 4];
+        assert_eq!(source.gain(6.0).fill(&mut buf), 3);
+        let expected = [0.5, -0.25, 0.1].iter().map(|sample| sample * 1.9953);
+        for (sample, expected) in buf.iter().zip(expected) {
+            assert!((sample - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn normalize_hits_peak_and_rms_targets() {
+        let samples: Vec<Real> = (0 .. 4800)
+            .map(|n| 0.3 * (2.0 * PI * n as Real / 48.0).sin())
+            .collect();
+
+        let source = SamplesBuffer::new(1, 48000, samples.clone());
+        let normalized: Vec<Real> =
+            source.normalize(Normalize::Peak(-1.0)).unwrap().collect();
+        assert!((amplitude_to_db(peak(&normalized)) + 1.0).abs() < 1e-4);
+
+        let source = SamplesBuffer::new(1, 48000, samples);
+        let normalized: Vec<Real> =
+            source.normalize(Normalize::Rms(-18.0)).unwrap().collect();
+        assert!((amplitude_to_db(rms(&normalized)) + 18.0).abs() < 1e-4);
+
+        let silent = SamplesBuffer::new(1, 48000, vec![0.0; 16]);
+        let gain = silent.normalize(Normalize::Peak(0.0)).unwrap();
+        assert_eq!(gain.amplitude(), 1.0);
+    }
+
+    #[test]
+    fn normalize_rejects_infinite_sources() {
+        let wave = SineWaveBuilder::default().finish();
+        assert!(wave.normalize(Normalize::Peak(0.0)).is_none());
+    }
+
+    #[test]
+    fn dc_blocker_removes_saw_offset() {
+        let saw = || SawWaveBuilder::default().freq(4800.0).finish();
+        let raw: Vec<Real> = saw().take(48000).collect();
+        let mean = |samples: &[Real]| {
+            samples.iter().sum::<Real>() / samples.len() as Real
+        };
+        assert!((mean(&raw[24000 ..]) + 0.1).abs() < 1e-9);
+
+        let mut blocked = DcBlockerBuilder::default().finish(saw());
+        let mut filtered = vec![0.0; 48000];
+        assert_eq!(blocked.fill(&mut filtered), 48000);
+        assert!(mean(&filtered[24000 ..]).abs() < 1e-3);
+        assert!(peak(&filtered[24000 ..]) > 0.9);
+    }
+}
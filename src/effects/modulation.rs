@@ -0,0 +1,242 @@
+use crate::{
+    num::Real,
+    source::{option_min, Source},
+};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+struct Frames<C, M>
+where
+    C: Source,
+    M: Source,
+{
+    carrier: C,
+    modulator: M,
+    channels: u16,
+    channel: u16,
+    carrier_frame: Vec<Real>,
+    modulator_frame: Vec<Real>,
+}
+
+impl<C, M> Frames<C, M>
+where
+    C: Source,
+    M: Source,
+{
+    fn new(carrier: C, modulator: M) -> Self {
+        let channels = carrier.channels().max(modulator.channels());
+        Self {
+            carrier,
+            modulator,
+            channels,
+            channel: 0,
+            carrier_frame: Vec::new(),
+            modulator_frame: Vec::new(),
+        }
+    }
+
+    fn read_frames(&mut self) -> bool {
+        let channels = usize::from(self.carrier.channels());
+        self.carrier_frame.clear();
+        self.carrier_frame.extend((&mut self.carrier).take(channels));
+
+        let channels = usize::from(self.modulator.channels());
+        self.modulator_frame.clear();
+        self.modulator_frame.extend((&mut self.modulator).take(channels));
+        !self.carrier_frame.is_empty() && !self.modulator_frame.is_empty()
+    }
+
+    fn next_pair(&mut self) -> Option<(Real, Real)> {
+        if self.channel == 0 && !self.read_frames() {
+            return None;
+        }
+
+        let channel = usize::from(self.channel);
+        let carrier = self.carrier_frame[channel % self.carrier_frame.len()];
+        let modulator =
+            self.modulator_frame[channel % self.modulator_frame.len()];
+        self.channel = (self.channel + 1) % self.channels;
+        Some((carrier, modulator))
+    }
+
+    fn len(&self) -> Option<usize> {
+        option_min(self.carrier.len(), Some(self.modulator.len()))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        option_min(self.carrier.duration(), Some(self.modulator.duration()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RingMod<C, M>
+where
+    C: Source,
+    M: Source,
+{
+    frames: Frames<C, M>,
+}
+
+impl<C, M> RingMod<C, M>
+where
+    C: Source,
+    M: Source,
+{
+    pub fn new(carrier: C, modulator: M) -> Self {
+        Self { frames: Frames::new(carrier, modulator) }
+    }
+}
+
+impl<C, M> Iterator for RingMod<C, M>
+where
+    C: Source,
+    M: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (carrier, modulator) = self.frames.next_pair()?;
+        Some(carrier * modulator)
+    }
+}
+
+impl<C, M> Source for RingMod<C, M>
+where
+    C: Source,
+    M: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.frames.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.frames.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.frames.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.frames.carrier.sample_rate()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AmplitudeMod<C, M>
+where
+    C: Source,
+    M: Source,
+{
+    frames: Frames<C, M>,
+    depth: Real,
+}
+
+impl<C, M> Iterator for AmplitudeMod<C, M>
+where
+    C: Source,
+    M: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (carrier, modulator) = self.frames.next_pair()?;
+        Some(carrier * (1.0 - self.depth * 0.5 * (1.0 - modulator)))
+    }
+}
+
+impl<C, M> Source for AmplitudeMod<C, M>
+where
+    C: Source,
+    M: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.frames.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.frames.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.frames.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.frames.carrier.sample_rate()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AmplitudeModBuilder {
+    depth: Real,
+}
+
+impl Default for AmplitudeModBuilder {
+    fn default() -> Self {
+        Self { depth: 1.0 }
+    }
+}
+
+impl AmplitudeModBuilder {
+    pub fn depth(&mut self, depth: Real) -> &mut Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn get_depth(&self) -> Real {
+        self.depth
+    }
+
+    pub fn finish<C, M>(&self, carrier: C, modulator: M) -> AmplitudeMod<C, M>
+    where
+        C: Source,
+        M: Source,
+    {
+        AmplitudeMod {
+            frames: Frames::new(carrier, modulator),
+            depth: self.depth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AmplitudeModBuilder, RingMod};
+    use crate::{
+        num::Real,
+        source::{SamplesBuffer, Source},
+    };
+
+    #[test]
+    fn ring_mod_multiplies_frames() {
+        let carrier = SamplesBuffer::new(1, 48000, vec![1.0, 2.0, 3.0, 4.0]);
+        let modulator = SamplesBuffer::new(1, 48000, vec![0.5, -1.0, 0.0, 2.0]);
+        let ring = RingMod::new(carrier, modulator);
+        assert_eq!(ring.len(), Some(4));
+        assert_eq!(ring.collect::<Vec<_>>(), [0.5, -2.0, 0.0, 8.0]);
+
+        let carrier = SamplesBuffer::new(2, 48000, vec![1.0, 2.0, 3.0, 4.0]);
+        let modulator = SamplesBuffer::new(1, 48000, vec![2.0, -1.0, 5.0]);
+        let ring = RingMod::new(carrier, modulator);
+        assert_eq!(ring.channels(), 2);
+        assert_eq!(ring.len(), Some(2));
+        assert_eq!(ring.collect::<Vec<_>>(), [2.0, 4.0, -3.0, -4.0]);
+    }
+
+    #[test]
+    fn amplitude_mod_scales_by_depth() {
+        let modulated = |depth: Real| {
+            let carrier = SamplesBuffer::new(1, 48000, vec![2.0; 4]);
+            let modulator =
+                SamplesBuffer::new(1, 48000, vec![1.0, 0.0, -1.0, 0.5]);
+            AmplitudeModBuilder::default()
+                .depth(depth)
+                .finish(carrier, modulator)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(modulated(1.0), [2.0, 1.0, 0.0, 1.5]);
+        assert_eq!(modulated(0.5), [2.0, 1.5, 1.0, 1.75]);
+        assert_eq!(modulated(0.0), [2.0; 4]);
+    }
+}
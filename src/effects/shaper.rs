@@ -0,0 +1,243 @@
+use crate::{num::Real, source::Source};
+use std::{fmt, sync::Arc, time::Duration};
+
+#[derive(Clone)]
+pub enum Curve {
+    SoftClip,
+    HardClip,
+    Foldback,
+    Tube,
+    Custom(Arc<dyn Fn(Real) -> Real + Send + Sync>),
+}
+
+impl Curve {
+    pub fn custom<F>(function: F) -> Self
+    where
+        F: Fn(Real) -> Real + Send + Sync + 'static,
+    {
+        Curve::Custom(Arc::new(function))
+    }
+
+    pub fn apply(&self, input: Real) -> Real {
+        match self {
+            Curve::SoftClip => input.tanh(),
+            Curve::HardClip => input.clamp(-1.0, 1.0),
+            Curve::Foldback => {
+                1.0 - ((input + 1.0).rem_euclid(4.0) - 2.0).abs()
+            },
+            Curve::Tube => {
+                if input >= 0.0 {
+                    1.0 - (-input).exp()
+                } else {
+                    (0.5 * input).exp() - 1.0
+                }
+            },
+            Curve::Custom(function) => function(input),
+        }
+    }
+}
+
+impl fmt::Debug for Curve {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Curve::SoftClip => fmt.pad("SoftClip"),
+            Curve::HardClip => fmt.pad("HardClip"),
+            Curve::Foldback => fmt.pad("Foldback"),
+            Curve::Tube => fmt.pad("Tube"),
+            Curve::Custom(_) => fmt.pad("Custom"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WaveShaper<S>
+where
+    S: Source,
+{
+    inner: S,
+    curve: Curve,
+    drive: Real,
+    antialiasing: usize,
+    channel: usize,
+    last: Vec<Real>,
+}
+
+impl<S> WaveShaper<S>
+where
+    S: Source,
+{
+    fn shape(&mut self, input: Real) -> Real {
+        let input = input * self.drive;
+        let prev = self.last[self.channel];
+        self.last[self.channel] = input;
+        self.channel = (self.channel + 1) % self.last.len();
+
+        if self.antialiasing <= 1 {
+            return self.curve.apply(input);
+        }
+
+        let steps = self.antialiasing as Real;
+        let mut sum = 0.0;
+        for i in 1 ..= self.antialiasing {
+            let interpolated = prev + (input - prev) * i as Real / steps;
+            sum += self.curve.apply(interpolated);
+        }
+        sum / steps
+    }
+}
+
+impl<S> Iterator for WaveShaper<S>
+where
+    S: Source,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.inner.next()?;
+        Some(self.shape(input))
+    }
+}
+
+impl<S> Source for WaveShaper<S>
+where
+    S: Source,
+{
+    fn len(&self) -> Option<usize> {
+        self.inner.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let len = self.inner.fill(buf);
+        for sample in &mut buf[.. len] {
+            *sample = self.shape(*sample);
+        }
+        len
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WaveShaperBuilder {
+    curve: Curve,
+    drive: Real,
+    antialiasing: usize,
+}
+
+impl Default for WaveShaperBuilder {
+    fn default() -> Self {
+        Self { curve: Curve::SoftClip, drive: 1.0, antialiasing: 1 }
+    }
+}
+
+impl WaveShaperBuilder {
+    pub fn curve(&mut self, curve: Curve) -> &mut Self {
+        self.curve = curve;
+        self
+    }
+
+    pub fn drive(&mut self, drive: Real) -> &mut Self {
+        self.drive = drive;
+        self
+    }
+
+    pub fn antialiasing(&mut self, antialiasing: usize) -> &mut Self {
+        self.antialiasing = antialiasing;
+        self
+    }
+
+    pub fn get_curve(&self) -> &Curve {
+        &self.curve
+    }
+
+    pub fn get_drive(&self) -> Real {
+        self.drive
+    }
+
+    pub fn get_antialiasing(&self) -> usize {
+        self.antialiasing
+    }
+
+    pub fn finish<S>(&self, source: S) -> WaveShaper<S>
+    where
+        S: Source,
+    {
+        let channels = usize::from(source.channels().max(1));
+        WaveShaper {
+            inner: source,
+            curve: self.curve.clone(),
+            drive: self.drive,
+            antialiasing: self.antialiasing,
+            channel: 0,
+            last: vec![0.0; channels],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Curve, WaveShaperBuilder};
+    use crate::{num::Real, source::SamplesBuffer};
+
+    fn assert_curve(curve: Curve, points: &[(Real, Real)]) {
+        for &(input, output) in points {
+            let shaped = curve.apply(input);
+            assert!(
+                (shaped - output).abs() < 1e-6,
+                "{:?}({}) = {} != {}",
+                curve,
+                input,
+                shaped,
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn curves_follow_transfer_functions() {
+        let e = (1.0 as Real).exp();
+        assert_curve(
+            Curve::SoftClip,
+            &[(0.0, 0.0), (1.0, (1.0 as Real).tanh()), (-20.0, -1.0)],
+        );
+        assert_curve(Curve::HardClip, &[(0.5, 0.5), (2.0, 1.0), (-3.0, -1.0)]);
+        assert_curve(
+            Curve::Foldback,
+            &[(0.5, 0.5), (1.5, 0.5), (-1.5, -0.5), (3.0, -1.0), (4.5, 0.5)],
+        );
+        assert_curve(
+            Curve::Tube,
+            &[(0.0, 0.0), (1.0, 1.0 - 1.0 / e), (-2.0, 1.0 / e - 1.0)],
+        );
+        assert_curve(Curve::custom(|input| input * input), &[(-3.0, 9.0)]);
+    }
+
+    #[test]
+    fn shaper_applies_drive_and_antialiasing() {
+        let source = || SamplesBuffer::new(1, 48000, vec![0.25, 1.0, 1.0]);
+        let shaped: Vec<Real> = WaveShaperBuilder::default()
+            .curve(Curve::HardClip)
+            .drive(2.0)
+            .finish(source())
+            .collect();
+        assert_eq!(shaped, [0.5, 1.0, 1.0]);
+
+        let shaped: Vec<Real> = WaveShaperBuilder::default()
+            .curve(Curve::HardClip)
+            .drive(2.0)
+            .antialiasing(4)
+            .finish(source())
+            .collect();
+        assert_eq!(shaped, [0.3125, 0.96875, 1.0]);
+    }
+}
@@ -0,0 +1,710 @@
+mod aiff;
+mod au;
+mod flac;
+
+pub use aiff::AiffSink;
+pub use au::AuSink;
+pub use flac::FlacSink;
+
+use crate::{
+    num::{Real, Sample},
+    source::Source,
+};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufWriter, Seek, Write},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SampleFormat {
+    Int,
+    Float,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ClipPolicy {
+    Clip,
+    Error,
+    Normalize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PcmFormat {
+    U8,
+    I16,
+    F32,
+}
+
+impl PcmFormat {
+    pub fn bits_per_sample(self) -> u16 {
+        match self {
+            PcmFormat::U8 => 8,
+            PcmFormat::I16 => 16,
+            PcmFormat::F32 => 32,
+        }
+    }
+
+    pub fn sample_format(self) -> SampleFormat {
+        match self {
+            PcmFormat::F32 => SampleFormat::Float,
+            _ => SampleFormat::Int,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExportStats {
+    pub samples: usize,
+    pub clipped: usize,
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    Wav(hound::Error),
+    UnsupportedFormat { bits_per_sample: u16, sample_format: SampleFormat },
+    UnsupportedChannels { channels: u16 },
+    Clipped(ExportStats),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::Io(err) => write!(fmt, "{}", err),
+            ExportError::Wav(err) => write!(fmt, "{}", err),
+            ExportError::UnsupportedFormat {
+                bits_per_sample,
+                sample_format,
+            } => write!(
+                fmt,
+                "Unsupported sample format: {} bits {:?}",
+                bits_per_sample, sample_format
+            ),
+            ExportError::UnsupportedChannels { channels } => {
+                write!(fmt, "Unsupported channel count: {}", channels)
+            },
+            ExportError::Clipped(stats) => write!(
+                fmt,
+                "Clipped {} out of {} samples",
+                stats.clipped, stats.samples
+            ),
+        }
+    }
+}
+
+impl Error for ExportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExportError::Io(err) => Some(err),
+            ExportError::Wav(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> Self {
+        ExportError::Io(err)
+    }
+}
+
+impl From<hound::Error> for ExportError {
+    fn from(err: hound::Error) -> Self {
+        ExportError::Wav(err)
+    }
+}
+
+pub trait SourceSink {
+    fn write<S, W>(
+        &self,
+        source: S,
+        target: W,
+    ) -> Result<ExportStats, ExportError>
+    where
+        S: Source,
+        W: Write;
+}
+
+#[derive(Debug, Clone)]
+pub struct WavExportOptions {
+    bits_per_sample: u16,
+    sample_format: SampleFormat,
+    dither: bool,
+    clipping: ClipPolicy,
+}
+
+impl Default for WavExportOptions {
+    fn default() -> Self {
+        Self {
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+            dither: false,
+            clipping: ClipPolicy::Clip,
+        }
+    }
+}
+
+impl WavExportOptions {
+    pub fn bits_per_sample(&mut self, bits_per_sample: u16) -> &mut Self {
+        self.bits_per_sample = bits_per_sample;
+        self
+    }
+
+    pub fn sample_format(&mut self, sample_format: SampleFormat) -> &mut Self {
+        self.sample_format = sample_format;
+        self
+    }
+
+    pub fn dither(&mut self, dither: bool) -> &mut Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn clipping(&mut self, clipping: ClipPolicy) -> &mut Self {
+        self.clipping = clipping;
+        self
+    }
+
+    pub fn get_bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+
+    pub fn get_sample_format(&self) -> SampleFormat {
+        self.sample_format
+    }
+
+    pub fn get_dither(&self) -> bool {
+        self.dither
+    }
+
+    pub fn get_clipping(&self) -> ClipPolicy {
+        self.clipping
+    }
+
+    pub fn write<S, W>(
+        &self,
+        source: S,
+        target: W,
+    ) -> Result<ExportStats, ExportError>
+    where
+        S: Source,
+        W: Write + Seek,
+    {
+        let mut quantizer = Quantizer::new(
+            self.bits_per_sample,
+            self.sample_format,
+            self.dither,
+        )?;
+        let mut writer = hound::WavWriter::new(
+            target,
+            hound::WavSpec {
+                channels: source.channels(),
+                sample_rate: source.sample_rate(),
+                bits_per_sample: self.bits_per_sample,
+                sample_format: match self.sample_format {
+                    SampleFormat::Int => hound::SampleFormat::Int,
+                    SampleFormat::Float => hound::SampleFormat::Float,
+                },
+            },
+        )?;
+
+        let mut write = |sample| match quantizer.quantize(sample) {
+            Quantized::Float(sample) => writer.write_sample(sample),
+            Quantized::Int(sample) => match quantizer.bits_per_sample {
+                8 => writer.write_sample(sample as i8),
+                16 => writer.write_sample(sample as i16),
+                _ => writer.write_sample(sample),
+            },
+        };
+
+        for_each_sample(source, self.clipping, &mut write)?;
+        writer.finalize()?;
+        quantizer.finish(self.clipping)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RawPcmSink {
+    format: PcmFormat,
+    endianness: Endianness,
+    dither: bool,
+    clipping: ClipPolicy,
+}
+
+impl Default for RawPcmSink {
+    fn default() -> Self {
+        Self {
+            format: PcmFormat::F32,
+            endianness: Endianness::Little,
+            dither: false,
+            clipping: ClipPolicy::Clip,
+        }
+    }
+}
+
+impl RawPcmSink {
+    pub fn format(&mut self, format: PcmFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    pub fn endianness(&mut self, endianness: Endianness) -> &mut Self {
+        self.endianness = endianness;
+        self
+    }
+
+    pub fn dither(&mut self, dither: bool) -> &mut Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn clipping(&mut self, clipping: ClipPolicy) -> &mut Self {
+        self.clipping = clipping;
+        self
+    }
+
+    pub fn get_format(&self) -> PcmFormat {
+        self.format
+    }
+
+    pub fn get_endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    pub fn get_dither(&self) -> bool {
+        self.dither
+    }
+
+    pub fn get_clipping(&self) -> ClipPolicy {
+        self.clipping
+    }
+}
+
+impl SourceSink for RawPcmSink {
+    fn write<S, W>(
+        &self,
+        source: S,
+        target: W,
+    ) -> Result<ExportStats, ExportError>
+    where
+        S: Source,
+        W: Write,
+    {
+        let mut target = BufWriter::new(target);
+        let stats = write_pcm(
+            source,
+            &mut target,
+            self.format,
+            self.endianness,
+            self.dither,
+            self.clipping,
+        )?;
+        target.flush()?;
+        Ok(stats)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WavStreamSink {
+    format: PcmFormat,
+    dither: bool,
+    clipping: ClipPolicy,
+}
+
+impl Default for WavStreamSink {
+    fn default() -> Self {
+        Self {
+            format: PcmFormat::F32,
+            dither: false,
+            clipping: ClipPolicy::Clip,
+        }
+    }
+}
+
+impl WavStreamSink {
+    pub fn format(&mut self, format: PcmFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    pub fn dither(&mut self, dither: bool) -> &mut Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn clipping(&mut self, clipping: ClipPolicy) -> &mut Self {
+        self.clipping = clipping;
+        self
+    }
+
+    pub fn get_format(&self) -> PcmFormat {
+        self.format
+    }
+
+    pub fn get_dither(&self) -> bool {
+        self.dither
+    }
+
+    pub fn get_clipping(&self) -> ClipPolicy {
+        self.clipping
+    }
+}
+
+impl SourceSink for WavStreamSink {
+    fn write<S, W>(
+        &self,
+        source: S,
+        target: W,
+    ) -> Result<ExportStats, ExportError>
+    where
+        S: Source,
+        W: Write,
+    {
+        let mut target = BufWriter::new(target);
+        let channels = source.channels();
+        let rate = source.sample_rate();
+        let bits = self.format.bits_per_sample();
+        let block_align = channels * (bits / 8);
+        let tag: u16 = match self.format {
+            PcmFormat::F32 => 3,
+            _ => 1,
+        };
+        let header_len = 36;
+        let block = u32::from(block_align.max(1));
+        let data_len = (u32::MAX - header_len) / block * block;
+
+        target.write_all(b"RIFF")?;
+        target.write_all(&(data_len + header_len).to_le_bytes())?;
+        target.write_all(b"WAVEfmt ")?;
+        target.write_all(&16u32.to_le_bytes())?;
+        target.write_all(&tag.to_le_bytes())?;
+        target.write_all(&channels.to_le_bytes())?;
+        target.write_all(&rate.to_le_bytes())?;
+        target.write_all(&(rate * u32::from(block_align)).to_le_bytes())?;
+        target.write_all(&block_align.to_le_bytes())?;
+        target.write_all(&bits.to_le_bytes())?;
+        target.write_all(b"data")?;
+        target.write_all(&data_len.to_le_bytes())?;
+
+        let stats = write_pcm(
+            source,
+            &mut target,
+            self.format,
+            Endianness::Little,
+            self.dither,
+            self.clipping,
+        )?;
+        target.flush()?;
+        Ok(stats)
+    }
+}
+
+fn write_pcm<S, W>(
+    source: S,
+    target: &mut W,
+    format: PcmFormat,
+    endianness: Endianness,
+    dither: bool,
+    clipping: ClipPolicy,
+) -> Result<ExportStats, ExportError>
+where
+    S: Source,
+    W: Write,
+{
+    let mut quantizer = Quantizer::new(
+        format.bits_per_sample(),
+        format.sample_format(),
+        dither,
+    )?;
+
+    for_each_sample(source, clipping, |sample| {
+        match quantizer.quantize(sample) {
+            Quantized::Float(value) => match endianness {
+                Endianness::Little => target.write_all(&value.to_le_bytes()),
+                Endianness::Big => target.write_all(&value.to_be_bytes()),
+            },
+            Quantized::Int(value) if format == PcmFormat::U8 => {
+                target.write_all(&[(value + 128) as u8])
+            },
+            Quantized::Int(value) => match endianness {
+                Endianness::Little => {
+                    target.write_all(&(value as i16).to_le_bytes())
+                },
+                Endianness::Big => {
+                    target.write_all(&(value as i16).to_be_bytes())
+                },
+            },
+        }
+    })?;
+
+    quantizer.finish(clipping)
+}
+
+pub(crate) fn for_each_sample<S, F, E>(
+    source: S,
+    clipping: ClipPolicy,
+    mut consume: F,
+) -> Result<(), ExportError>
+where
+    S: Source,
+    F: FnMut(Real) -> Result<(), E>,
+    ExportError: From<E>,
+{
+    match clipping {
+        ClipPolicy::Normalize => {
+            let samples = source.collect::<Vec<_>>();
+            let gain = normalize_gain(&samples);
+            for sample in samples {
+                consume(sample * gain)?;
+            }
+        },
+        ClipPolicy::Error => {
+            for (index, sample) in source.enumerate() {
+                if sample.abs() > 1.0 {
+                    let stats = ExportStats { samples: index + 1, clipped: 1 };
+                    return Err(ExportError::Clipped(stats));
+                }
+                consume(sample)?;
+            }
+        },
+        ClipPolicy::Clip => {
+            for sample in source {
+                consume(sample)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+pub(crate) fn normalize_gain(samples: &[Real]) -> Real {
+    let peak = samples.iter().fold(0.0, |peak, sample| sample.abs().max(peak));
+    if peak > 1.0 {
+        1.0 / peak
+    } else {
+        1.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Quantized {
+    Int(i32),
+    Float(f32),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Quantizer {
+    pub(crate) bits_per_sample: u16,
+    float: bool,
+    dither: Option<u64>,
+    stats: ExportStats,
+}
+
+impl Quantizer {
+    pub(crate) fn new(
+        bits_per_sample: u16,
+        sample_format: SampleFormat,
+        dither: bool,
+    ) -> Result<Self, ExportError> {
+        let valid = match sample_format {
+            SampleFormat::Int => matches!(bits_per_sample, 8 | 16 | 24 | 32),
+            SampleFormat::Float => bits_per_sample == 32,
+        };
+        if !valid {
+            return Err(ExportError::UnsupportedFormat {
+                bits_per_sample,
+                sample_format,
+            });
+        }
+
+        Ok(Self {
+            bits_per_sample,
+            float: sample_format == SampleFormat::Float,
+            dither: if dither { Some(0x2545_f491_4f6c_dd1d) } else { None },
+            stats: ExportStats { samples: 0, clipped: 0 },
+        })
+    }
+
+    fn noise(&mut self) -> Real {
+        match &mut self.dither {
+            Some(state) => {
+                let mut uniform = || {
+                    *state ^= *state << 13;
+                    *state ^= *state >> 7;
+                    *state ^= *state << 17;
+                    (*state >> 11) as Real / (1u64 << 53) as Real
+                };
+                uniform() - uniform()
+            },
+            None => 0.0,
+        }
+    }
+
+    pub(crate) fn quantize(&mut self, sample: Real) -> Quantized {
+        self.stats.samples += 1;
+        let sample = if sample.abs() > 1.0 {
+            self.stats.clipped += 1;
+            sample.clamp(-1.0, 1.0)
+        } else {
+            sample
+        };
+
+        if self.float {
+            return Quantized::Float(f32::from_real(sample));
+        }
+
+        let max = ((1i64 << (self.bits_per_sample - 1)) - 1) as Real;
+        let value = (sample * max + self.noise()).round();
+        Quantized::Int(value.clamp(-max - 1.0, max) as i32)
+    }
+
+    pub(crate) fn finish(
+        &self,
+        clipping: ClipPolicy,
+    ) -> Result<ExportStats, ExportError> {
+        if clipping == ClipPolicy::Error && self.stats.clipped > 0 {
+            Err(ExportError::Clipped(self.stats))
+        } else {
+            Ok(self.stats)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn signal(channels: u16, frames: usize) -> Vec<Real> {
+    (0 .. frames * usize::from(channels))
+        .map(|i| {
+            let t = i as Real;
+            0.8 * (t * 0.031).sin() + 0.15 * (t * 0.57).cos()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        signal,
+        ClipPolicy,
+        Endianness,
+        ExportError,
+        PcmFormat,
+        RawPcmSink,
+        SourceSink,
+        WavStreamSink,
+    };
+    use crate::{num::Sample, source::SamplesBuffer};
+    use std::io::Cursor;
+
+    #[test]
+    fn clip_error_stops_at_first_clipped_sample() {
+        let mut samples = vec![0.25; 10];
+        samples[3] = 1.5;
+        let source = SamplesBuffer::new(1, 48000, samples);
+        let mut bytes = Vec::new();
+        let result = RawPcmSink::default()
+            .format(PcmFormat::I16)
+            .clipping(ClipPolicy::Error)
+            .write(source, &mut bytes);
+
+        match result {
+            Err(ExportError::Clipped(stats)) => {
+                assert_eq!(stats.samples, 4);
+                assert_eq!(stats.clipped, 1);
+            },
+            other => panic!("expected clipping error, got {:?}", other),
+        }
+        assert_eq!(bytes.len(), 3 * 2);
+    }
+
+    #[test]
+    fn wav_stream_round_trip_int() {
+        let samples = signal(2, 300);
+        let mut bytes = Vec::new();
+        WavStreamSink::default()
+            .format(PcmFormat::I16)
+            .write(SamplesBuffer::new(2, 32000, samples.clone()), &mut bytes)
+            .unwrap();
+
+        let reader = hound::WavReader::new(Cursor::new(bytes)).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.channels, 2);
+        assert_eq!(spec.sample_rate, 32000);
+        assert_eq!(spec.bits_per_sample, 16);
+        assert_eq!(spec.sample_format, hound::SampleFormat::Int);
+
+        let decoded: Vec<i16> = reader
+            .into_samples()
+            .take(samples.len())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected: Vec<i16> = samples
+            .iter()
+            .map(|sample| (sample * 32767.0).round() as i16)
+            .collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn wav_stream_round_trip_float() {
+        let samples = signal(1, 300);
+        let mut bytes = Vec::new();
+        WavStreamSink::default()
+            .format(PcmFormat::F32)
+            .write(SamplesBuffer::new(1, 44100, samples.clone()), &mut bytes)
+            .unwrap();
+
+        let reader = hound::WavReader::new(Cursor::new(bytes)).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.channels, 1);
+        assert_eq!(spec.bits_per_sample, 32);
+        assert_eq!(spec.sample_format, hound::SampleFormat::Float);
+
+        let decoded: Vec<f32> = reader
+            .into_samples()
+            .take(samples.len())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected: Vec<f32> =
+            samples.iter().map(|&sample| f32::from_real(sample)).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn raw_pcm_layouts() {
+        let samples = vec![0.5, -1.0, 0.25, 1.0];
+        let source = || SamplesBuffer::new(2, 8000, samples.clone());
+
+        let mut bytes = Vec::new();
+        RawPcmSink::default()
+            .format(PcmFormat::U8)
+            .write(source(), &mut bytes)
+            .unwrap();
+        assert_eq!(bytes, [192, 1, 160, 255]);
+
+        let mut bytes = Vec::new();
+        RawPcmSink::default()
+            .format(PcmFormat::I16)
+            .endianness(Endianness::Big)
+            .write(source(), &mut bytes)
+            .unwrap();
+        assert_eq!(bytes, [0x40, 0x00, 0x80, 0x01, 0x20, 0x00, 0x7f, 0xff]);
+
+        let mut bytes = Vec::new();
+        let stats = RawPcmSink::default().write(source(), &mut bytes).unwrap();
+        assert_eq!(stats.samples, 4);
+        assert_eq!(stats.clipped, 0);
+        let decoded: Vec<f32> = bytes
+            .chunks(4)
+            .map(|chunk| {
+                f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+            })
+            .collect();
+        assert_eq!(decoded, [0.5, -1.0, 0.25, 1.0]);
+    }
+}
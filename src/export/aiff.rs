@@ -0,0 +1,300 @@
+use super::{
+    for_each_sample,
+    ClipPolicy,
+    ExportError,
+    ExportStats,
+    Quantized,
+    Quantizer,
+    SampleFormat,
+    SourceSink,
+};
+use crate::source::Source;
+use std::io::{BufWriter, Write};
+
+fn extended(rate: u32) -> [u8; 10] {
+    let mut bytes = [0; 10];
+    if rate == 0 {
+        return bytes;
+    }
+    let shift = rate.leading_zeros();
+    let exponent = 16383 + 31 - shift as u16;
+    let mantissa = u64::from(rate) << (32 + shift);
+    bytes[.. 2].copy_from_slice(&exponent.to_be_bytes());
+    bytes[2 ..].copy_from_slice(&mantissa.to_be_bytes());
+    bytes
+}
+
+#[derive(Debug, Clone)]
+pub struct AiffSink {
+    bits_per_sample: u16,
+    sample_format: SampleFormat,
+    dither: bool,
+    clipping: ClipPolicy,
+}
+
+impl Default for AiffSink {
+    fn default() -> Self {
+        Self {
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+            dither: false,
+            clipping: ClipPolicy::Clip,
+        }
+    }
+}
+
+impl AiffSink {
+    pub fn bits_per_sample(&mut self, bits_per_sample: u16) -> &mut Self {
+        self.bits_per_sample = bits_per_sample;
+        self
+    }
+
+    pub fn sample_format(&mut self, sample_format: SampleFormat) -> &mut Self {
+        self.sample_format = sample_format;
+        self
+    }
+
+    pub fn dither(&mut self, dither: bool) -> &mut Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn clipping(&mut self, clipping: ClipPolicy) -> &mut Self {
+        self.clipping = clipping;
+        self
+    }
+
+    pub fn get_bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+
+    pub fn get_sample_format(&self) -> SampleFormat {
+        self.sample_format
+    }
+
+    pub fn get_dither(&self) -> bool {
+        self.dither
+    }
+
+    pub fn get_clipping(&self) -> ClipPolicy {
+        self.clipping
+    }
+}
+
+impl SourceSink for AiffSink {
+    fn write<S, W>(
+        &self,
+        source: S,
+        target: W,
+    ) -> Result<ExportStats, ExportError>
+    where
+        S: Source,
+        W: Write,
+    {
+        let mut quantizer = Quantizer::new(
+            self.bits_per_sample,
+            self.sample_format,
+            self.dither,
+        )?;
+        let channels = source.channels();
+        let rate = source.sample_rate();
+        let bytes = usize::from(self.bits_per_sample / 8);
+
+        let mut data = Vec::new();
+        for_each_sample(source, self.clipping, |sample| {
+            match quantizer.quantize(sample) {
+                Quantized::Float(value) => {
+                    data.extend_from_slice(&value.to_be_bytes())
+                },
+                Quantized::Int(value) => {
+                    data.extend_from_slice(&value.to_be_bytes()[4 - bytes ..])
+                },
+            }
+            Ok::<_, ExportError>(())
+        })?;
+        let stats = quantizer.finish(self.clipping)?;
+
+        let compressed = self.sample_format == SampleFormat::Float;
+        let frames = (data.len() / bytes / usize::from(channels.max(1))) as u32;
+        let pad = data.len() % 2;
+        let ssnd_len = 8 + data.len() as u32;
+        let (comm_len, fver_len) = if compressed { (44, 12) } else { (18, 0) };
+        let form_len = 4 + fver_len + 8 + comm_len + 8 + ssnd_len + pad as u32;
+
+        let mut target = BufWriter::new(target);
+        target.write_all(b"FORM")?;
+        target.write_all(&form_len.to_be_bytes())?;
+        target.write_all(if compressed { b"AIFC" } else { b"AIFF" })?;
+        if compressed {
+            target.write_all(b"FVER")?;
+            target.write_all(&4u32.to_be_bytes())?;
+            target.write_all(&0xA280_5140u32.to_be_bytes())?;
+        }
+
+        target.write_all(b"COMM")?;
+        target.write_all(&comm_len.to_be_bytes())?;
+        target.write_all(&channels.to_be_bytes())?;
+        target.write_all(&frames.to_be_bytes())?;
+        target.write_all(&self.bits_per_sample.to_be_bytes())?;
+        target.write_all(&extended(rate))?;
+        if compressed {
+            target.write_all(b"fl32")?;
+            target.write_all(b"\x1532-bit floating point")?;
+        }
+
+        target.write_all(b"SSND")?;
+        target.write_all(&ssnd_len.to_be_bytes())?;
+        target.write_all(&[0; 8])?;
+        target.write_all(&data)?;
+        if pad == 1 {
+            target.write_all(&[0])?;
+        }
+        target.flush()?;
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AiffSink;
+    use crate::{
+        export::{signal, SampleFormat, SourceSink},
+        num::{Real, Sample},
+        source::SamplesBuffer,
+    };
+
+    struct Aiff {
+        channels: u16,
+        frames: u32,
+        bits_per_sample: u16,
+        sample_rate: u32,
+        compression: Option<[u8; 4]>,
+        data: Vec<u8>,
+    }
+
+    fn be_u16(bytes: &[u8]) -> u16 {
+        u16::from_be_bytes([bytes[0], bytes[1]])
+    }
+
+    fn be_u32(bytes: &[u8]) -> u32 {
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn parse(bytes: &[u8]) -> Aiff {
+        assert_eq!(&bytes[.. 4], b"FORM");
+        assert_eq!(be_u32(&bytes[4 ..]) as usize, bytes.len() - 8);
+        let compressed = match &bytes[8 .. 12] {
+            b"AIFF" => false,
+            b"AIFC" => true,
+            other => panic!("unexpected form type {:?}", other),
+        };
+
+        let mut aiff = Aiff {
+            channels: 0,
+            frames: 0,
+            bits_per_sample: 0,
+            sample_rate: 0,
+            compression: None,
+            data: Vec::new(),
+        };
+        let mut rest = &bytes[12 ..];
+        while !rest.is_empty() {
+            let len = be_u32(&rest[4 ..]) as usize;
+            let body = &rest[8 .. 8 + len];
+            match &rest[.. 4] {
+                b"COMM" => {
+                    aiff.channels = be_u16(body);
+                    aiff.frames = be_u32(&body[2 ..]);
+                    aiff.bits_per_sample = be_u16(&body[6 ..]);
+                    let exponent = i32::from(be_u16(&body[8 ..])) - 16383;
+                    let mantissa = u64::from_be_bytes([
+                        body[10], body[11], body[12], body[13], body[14],
+                        body[15], body[16], body[17],
+                    ]);
+                    aiff.sample_rate = (mantissa >> (63 - exponent)) as u32;
+                    if compressed {
+                        let mut kind = [0; 4];
+                        kind.copy_from_slice(&body[18 .. 22]);
+                        aiff.compression = Some(kind);
+                    }
+                },
+                b"SSND" => {
+                    assert_eq!(be_u32(body), 0);
+                    aiff.data = body[8 ..].to_vec();
+                },
+                _ => (),
+            }
+            rest = &rest[8 + len + len % 2 ..];
+        }
+        aiff
+    }
+
+    fn decode_int(data: &[u8], bits_per_sample: u16) -> Vec<i32> {
+        let bytes = usize::from(bits_per_sample / 8);
+        data.chunks(bytes)
+            .map(|chunk| {
+                let mut word = [0; 4];
+                word[.. bytes].copy_from_slice(chunk);
+                i32::from_be_bytes(word) >> (32 - bits_per_sample)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_int() {
+        for &bits_per_sample in &[8, 16, 24, 32] {
+            let samples = signal(3, 101);
+            let mut bytes = Vec::new();
+            AiffSink::default()
+                .bits_per_sample(bits_per_sample)
+                .write(
+                    SamplesBuffer::new(3, 22050, samples.clone()),
+                    &mut bytes,
+                )
+                .unwrap();
+
+            let aiff = parse(&bytes);
+            assert_eq!(aiff.channels, 3);
+            assert_eq!(aiff.frames, 101);
+            assert_eq!(aiff.bits_per_sample, bits_per_sample);
+            assert_eq!(aiff.sample_rate, 22050);
+            assert_eq!(aiff.compression, None);
+
+            let max = ((1i64 << (bits_per_sample - 1)) - 1) as Real;
+            let expected: Vec<i32> = samples
+                .iter()
+                .map(|sample| (sample * max).round() as i32)
+                .collect();
+            assert_eq!(decode_int(&aiff.data, bits_per_sample), expected);
+        }
+    }
+
+    #[test]
+    fn round_trip_float() {
+        let samples = signal(1, 77);
+        let mut bytes = Vec::new();
+        AiffSink::default()
+            .bits_per_sample(32)
+            .sample_format(SampleFormat::Float)
+            .write(SamplesBuffer::new(1, 48000, samples.clone()), &mut bytes)
+            .unwrap();
+
+        let aiff = parse(&bytes);
+        assert_eq!(aiff.channels, 1);
+        assert_eq!(aiff.frames, 77);
+        assert_eq!(aiff.sample_rate, 48000);
+        assert_eq!(aiff.compression, Some(*b"fl32"));
+
+        let decoded: Vec<f32> = aiff
+            .data
+            .chunks(4)
+            .map(|chunk| {
+                f32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+            })
+            .collect();
+        let expected: Vec<f32> =
+            samples.iter().map(|&sample| f32::from_real(sample)).collect();
+        assert_eq!(decoded, expected);
+    }
+}
@@ -0,0 +1,211 @@
+use super::{
+    for_each_sample,
+    ClipPolicy,
+    ExportError,
+    ExportStats,
+    Quantized,
+    Quantizer,
+    SampleFormat,
+    SourceSink,
+};
+use crate::source::Source;
+use std::io::{BufWriter, Write};
+
+#[derive(Debug, Clone)]
+pub struct AuSink {
+    bits_per_sample: u16,
+    sample_format: SampleFormat,
+    dither: bool,
+    clipping: ClipPolicy,
+}
+
+impl Default for AuSink {
+    fn default() -> Self {
+        Self {
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+            dither: false,
+            clipping: ClipPolicy::Clip,
+        }
+    }
+}
+
+impl AuSink {
+    pub fn bits_per_sample(&mut self, bits_per_sample: u16) -> &mut Self {
+        self.bits_per_sample = bits_per_sample;
+        self
+    }
+
+    pub fn sample_format(&mut self, sample_format: SampleFormat) -> &mut Self {
+        self.sample_format = sample_format;
+        self
+    }
+
+    pub fn dither(&mut self, dither: bool) -> &mut Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn clipping(&mut self, clipping: ClipPolicy) -> &mut Self {
+        self.clipping = clipping;
+        self
+    }
+
+    pub fn get_bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+
+    pub fn get_sample_format(&self) -> SampleFormat {
+        self.sample_format
+    }
+
+    pub fn get_dither(&self) -> bool {
+        self.dither
+    }
+
+    pub fn get_clipping(&self) -> ClipPolicy {
+        self.clipping
+    }
+}
+
+impl SourceSink for AuSink {
+    fn write<S, W>(
+        &self,
+        source: S,
+        target: W,
+    ) -> Result<ExportStats, ExportError>
+    where
+        S: Source,
+        W: Write,
+    {
+        let mut quantizer = Quantizer::new(
+            self.bits_per_sample,
+            self.sample_format,
+            self.dither,
+        )?;
+        let encoding: u32 = match (self.sample_format, self.bits_per_sample) {
+            (SampleFormat::Float, _) => 6,
+            (_, bits) => u32::from(bits / 8) + 1,
+        };
+        let bytes = usize::from(self.bits_per_sample / 8);
+
+        let mut target = BufWriter::new(target);
+        target.write_all(b".snd")?;
+        target.write_all(&24u32.to_be_bytes())?;
+        target.write_all(&u32::MAX.to_be_bytes())?;
+        target.write_all(&encoding.to_be_bytes())?;
+        target.write_all(&source.sample_rate().to_be_bytes())?;
+        target.write_all(&u32::from(source.channels()).to_be_bytes())?;
+
+        for_each_sample(source, self.clipping, |sample| {
+            match quantizer.quantize(sample) {
+                Quantized::Float(value) => {
+                    target.write_all(&value.to_be_bytes())
+                },
+                Quantized::Int(value) => {
+                    target.write_all(&value.to_be_bytes()[4 - bytes ..])
+                },
+            }
+        })?;
+        target.flush()?;
+
+        quantizer.finish(self.clipping)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AuSink;
+    use crate::{
+        export::{signal, SampleFormat, SourceSink},
+        num::{Real, Sample},
+        source::SamplesBuffer,
+    };
+
+    struct Au {
+        encoding: u32,
+        sample_rate: u32,
+        channels: u32,
+        data: Vec<u8>,
+    }
+
+    fn be_u32(bytes: &[u8]) -> u32 {
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn parse(bytes: &[u8]) -> Au {
+        assert_eq!(&bytes[.. 4], b".snd");
+        let offset = be_u32(&bytes[4 ..]) as usize;
+        let size = be_u32(&bytes[8 ..]);
+        let data = &bytes[offset ..];
+        assert!(size == u32::MAX || size as usize == data.len());
+        Au {
+            encoding: be_u32(&bytes[12 ..]),
+            sample_rate: be_u32(&bytes[16 ..]),
+            channels: be_u32(&bytes[20 ..]),
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn round_trip_int() {
+        for &(bits_per_sample, encoding) in &[(8, 2), (16, 3), (24, 4), (32, 5)]
+        {
+            let samples = signal(2, 64);
+            let mut bytes = Vec::new();
+            AuSink::default()
+                .bits_per_sample(bits_per_sample)
+                .write(SamplesBuffer::new(2, 8000, samples.clone()), &mut bytes)
+                .unwrap();
+
+            let au = parse(&bytes);
+            assert_eq!(au.encoding, encoding);
+            assert_eq!(au.sample_rate, 8000);
+            assert_eq!(au.channels, 2);
+
+            let width = usize::from(bits_per_sample / 8);
+            let decoded: Vec<i32> = au
+                .data
+                .chunks(width)
+                .map(|chunk| {
+                    let mut word = [0; 4];
+                    word[.. width].copy_from_slice(chunk);
+                    i32::from_be_bytes(word) >> (32 - bits_per_sample)
+                })
+                .collect();
+            let max = ((1i64 << (bits_per_sample - 1)) - 1) as Real;
+            let expected: Vec<i32> = samples
+                .iter()
+                .map(|sample| (sample * max).round() as i32)
+                .collect();
+            assert_eq!(decoded, expected);
+        }
+    }
+
+    #[test]
+    fn round_trip_float() {
+        let samples = signal(1, 50);
+        let mut bytes = Vec::new();
+        AuSink::default()
+            .bits_per_sample(32)
+            .sample_format(SampleFormat::Float)
+            .write(SamplesBuffer::new(1, 16000, samples.clone()), &mut bytes)
+            .unwrap();
+
+        let au = parse(&bytes);
+        assert_eq!(au.encoding, 6);
+        assert_eq!(au.sample_rate, 16000);
+        assert_eq!(au.channels, 1);
+
+        let decoded: Vec<f32> = au
+            .data
+            .chunks(4)
+            .map(|chunk| {
+                f32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+            })
+            .collect();
+        let expected: Vec<f32> =
+            samples.iter().map(|&sample| f32::from_real(sample)).collect();
+        assert_eq!(decoded, expected);
+    }
+}
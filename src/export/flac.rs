@@ -0,0 +1,497 @@
+use super::{
+    for_each_sample,
+    ClipPolicy,
+    ExportError,
+    ExportStats,
+    Quantized,
+    Quantizer,
+    SampleFormat,
+    SourceSink,
+};
+use crate::source::Source;
+use std::io::{BufWriter, Write};
+
+const MAX_ORDER: usize = 4;
+const MAX_PARTITION_ORDER: u32 = 8;
+const MAX_RICE_PARAM: u32 = 14;
+
+#[derive(Debug, Clone, Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, bits: u32) {
+        if bits == 0 {
+            return;
+        }
+        self.acc = (self.acc << bits) | (value & ((1 << bits) - 1));
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.bytes.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1 << self.bits) - 1;
+    }
+
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64, bits);
+    }
+
+    fn write_unary(&mut self, mut zeros: u64) {
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros as u32 + 1);
+    }
+
+    fn write_utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write(value, 8);
+            return;
+        }
+
+        let mut len = 2;
+        while value >= 1 << (5 * len + 1) {
+            len += 1;
+        }
+        let prefix = (0xFF00 >> len) & 0xFF;
+        self.write(prefix | value >> (6 * (len - 1)), 8);
+        for i in (0 .. len - 1).rev() {
+            self.write(0x80 | (value >> (6 * i)) & 0x3F, 8);
+        }
+    }
+
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0 .. 8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0 .. 8 {
+            crc =
+                if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+fn fold(residual: i64) -> u64 {
+    ((residual << 1) ^ (residual >> 63)) as u64
+}
+
+fn fixed_residuals(samples: &[i64], order: usize, residuals: &mut Vec<i64>) {
+    residuals.clear();
+    residuals.extend((order .. samples.len()).map(|i| {
+        let x = |back: usize| samples[i - back];
+        match order {
+            0 => x(0),
+            1 => x(0) - x(1),
+            2 => x(0) - 2 * x(1) + x(2),
+            3 => x(0) - 3 * x(1) + 3 * x(2) - x(3),
+            _ => x(0) - 4 * x(1) + 6 * x(2) - 4 * x(3) + x(4),
+        }
+    }));
+}
+
+fn rice_param(count: u64, sum: u64) -> (u32, u64) {
+    let mut best = (0, u64::MAX);
+    for param in 0 ..= MAX_RICE_PARAM {
+        let bits = count * (u64::from(param) + 1) + (sum >> param);
+        if bits < best.1 {
+            best = (param, bits);
+        }
+    }
+    best
+}
+
+#[derive(Debug, Clone)]
+struct Residual {
+    partition_order: u32,
+    params: Vec<u32>,
+    bits: u64,
+}
+
+fn plan_residual(
+    block_size: usize,
+    order: usize,
+    residuals: &[i64],
+) -> Residual {
+    let mut best: Option<Residual> = None;
+
+    for partition_order in 0 ..= MAX_PARTITION_ORDER {
+        let partitions = 1 << partition_order;
+        let len = block_size >> partition_order;
+        if block_size & (partitions - 1) != 0 || len <= order {
+            break;
+        }
+
+        let mut params = Vec::with_capacity(partitions);
+        let mut bits = 6;
+        let mut start = 0;
+        for i in 0 .. partitions {
+            let count = if i == 0 { len - order } else { len };
+            let sum = residuals[start .. start + count]
+                .iter()
+                .map(|&residual| fold(residual))
+                .sum();
+            let (param, param_bits) = rice_param(count as u64, sum);
+            params.push(param);
+            bits += 4 + param_bits;
+            start += count;
+        }
+
+        if best.as_ref().map(|best| bits < best.bits).unwrap_or(true) {
+            best = Some(Residual { partition_order, params, bits });
+        }
+    }
+
+    best.unwrap_or(Residual { partition_order: 0, params: vec![0], bits: 6 })
+}
+
+fn write_subframe(
+    writer: &mut BitWriter,
+    samples: &[i64],
+    bits_per_sample: u32,
+    residuals: &mut Vec<i64>,
+) {
+    if samples.iter().all(|&sample| sample == samples[0]) {
+        writer.write(0, 8);
+        writer.write_signed(samples[0], bits_per_sample);
+        return;
+    }
+
+    let mut best: Option<(usize, Residual)> = None;
+    for order in 0 ..= MAX_ORDER.min(samples.len() - 1) {
+        fixed_residuals(samples, order, residuals);
+        let plan = plan_residual(samples.len(), order, residuals);
+        let bits = plan.bits + order as u64 * u64::from(bits_per_sample);
+        let best_bits = best.as_ref().map(|(order, plan)| {
+            plan.bits + *order as u64 * u64::from(bits_per_sample)
+        });
+        if best_bits.map(|best_bits| bits < best_bits).unwrap_or(true) {
+            best = Some((order, plan));
+        }
+    }
+
+    let verbatim_bits = samples.len() as u64 * u64::from(bits_per_sample);
+    let (order, plan) = match best {
+        Some((order, plan))
+            if plan.bits + order as u64 * u64::from(bits_per_sample)
+                < verbatim_bits =>
+        {
+            (order, plan)
+        },
+        _ => {
+            writer.write(0b0000_0010, 8);
+            for &sample in samples {
+                writer.write_signed(sample, bits_per_sample);
+            }
+            return;
+        },
+    };
+
+    writer.write(0b0001_0000 | (order as u64) << 1, 8);
+    for &sample in &samples[.. order] {
+        writer.write_signed(sample, bits_per_sample);
+    }
+
+    fixed_residuals(samples, order, residuals);
+    writer.write(0, 2);
+    writer.write(u64::from(plan.partition_order), 4);
+    let len = samples.len() >> plan.partition_order;
+    let mut start = 0;
+    for (i, &param) in plan.params.iter().enumerate() {
+        let count = if i == 0 { len - order } else { len };
+        writer.write(u64::from(param), 4);
+        for &residual in &residuals[start .. start + count] {
+            let folded = fold(residual);
+            writer.write_unary(folded >> param);
+            writer.write(folded, param);
+        }
+        start += count;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Encoder {
+    channels: usize,
+    bits_per_sample: u32,
+    frame_number: u64,
+    block: Vec<i64>,
+    channel: Vec<i64>,
+    residuals: Vec<i64>,
+    writer: BitWriter,
+}
+
+impl Encoder {
+    fn encode_frame<W>(&mut self, target: &mut W) -> Result<(), ExportError>
+    where
+        W: Write,
+    {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        let rem = self.block.len() % self.channels;
+        if rem > 0 {
+            self.block.resize(self.block.len() + self.channels - rem, 0);
+        }
+
+        let block_size = self.block.len() / self.channels;
+        let size_code = match self.bits_per_sample {
+            8 => 0b001,
+            16 => 0b100,
+            _ => 0b110,
+        };
+
+        let writer = &mut self.writer;
+        writer.bytes.clear();
+        writer.write(0xFFF8, 16);
+        writer.write(0b0111, 4);
+        writer.write(0, 4);
+        writer.write(self.channels as u64 - 1, 4);
+        writer.write(size_code, 3);
+        writer.write(0, 1);
+        writer.write_utf8(self.frame_number);
+        writer.write(block_size as u64 - 1, 16);
+        let crc = crc8(&writer.bytes);
+        writer.write(u64::from(crc), 8);
+
+        for channel in 0 .. self.channels {
+            self.channel.clear();
+            self.channel
+                .extend(self.block.iter().skip(channel).step_by(self.channels));
+            write_subframe(
+                writer,
+                &self.channel,
+                self.bits_per_sample,
+                &mut self.residuals,
+            );
+        }
+
+        writer.align();
+        let crc = crc16(&writer.bytes);
+        writer.write(u64::from(crc), 16);
+        target.write_all(&writer.bytes)?;
+
+        self.block.clear();
+        self.frame_number += 1;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FlacSink {
+    bits_per_sample: u16,
+    block_size: usize,
+    dither: bool,
+    clipping: ClipPolicy,
+}
+
+impl Default for FlacSink {
+    fn default() -> Self {
+        Self {
+            bits_per_sample: 16,
+            block_size: 4096,
+            dither: false,
+            clipping: ClipPolicy::Clip,
+        }
+    }
+}
+
+impl FlacSink {
+    pub fn bits_per_sample(&mut self, bits_per_sample: u16) -> &mut Self {
+        self.bits_per_sample = bits_per_sample;
+        self
+    }
+
+    pub fn block_size(&mut self, block_size: usize) -> &mut Self {
+        self.block_size = block_size.clamp(16, 65535);
+        self
+    }
+
+    pub fn dither(&mut self, dither: bool) -> &mut Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn clipping(&mut self, clipping: ClipPolicy) -> &mut Self {
+        self.clipping = clipping;
+        self
+    }
+
+    pub fn get_bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+
+    pub fn get_block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn get_dither(&self) -> bool {
+        self.dither
+    }
+
+    pub fn get_clipping(&self) -> ClipPolicy {
+        self.clipping
+    }
+}
+
+impl SourceSink for FlacSink {
+    fn write<S, W>(
+        &self,
+        source: S,
+        target: W,
+    ) -> Result<ExportStats, ExportError>
+    where
+        S: Source,
+        W: Write,
+    {
+        if self.bits_per_sample == 32 {
+            return Err(ExportError::UnsupportedFormat {
+                bits_per_sample: self.bits_per_sample,
+                sample_format: SampleFormat::Int,
+            });
+        }
+        let mut quantizer = Quantizer::new(
+            self.bits_per_sample,
+            SampleFormat::Int,
+            self.dither,
+        )?;
+        let channels = source.channels();
+        if channels == 0 || channels > 8 {
+            return Err(ExportError::UnsupportedChannels { channels });
+        }
+        let channels = usize::from(channels);
+        let bits_per_sample = u32::from(self.bits_per_sample);
+
+        let mut target = BufWriter::new(target);
+        let mut header = BitWriter::default();
+        header.write(1, 1);
+        header.write(0, 7);
+        header.write(34, 24);
+        header.write(self.block_size as u64, 16);
+        header.write(self.block_size as u64, 16);
+        header.write(0, 24);
+        header.write(0, 24);
+        header.write(u64::from(source.sample_rate()), 20);
+        header.write(channels as u64 - 1, 3);
+        header.write(u64::from(bits_per_sample) - 1, 5);
+        header.write(0, 4);
+        header.write(0, 32);
+        target.write_all(b"fLaC")?;
+        target.write_all(&header.bytes)?;
+        target.write_all(&[0; 16])?;
+
+        let block_len = self.block_size * channels;
+        let mut encoder = Encoder {
+            channels,
+            bits_per_sample,
+            frame_number: 0,
+            block: Vec::with_capacity(block_len),
+            channel: Vec::with_capacity(self.block_size),
+            residuals: Vec::with_capacity(self.block_size),
+            writer: BitWriter::default(),
+        };
+
+        for_each_sample(source, self.clipping, |sample| {
+            if let Quantized::Int(value) = quantizer.quantize(sample) {
+                encoder.block.push(i64::from(value));
+            }
+            if encoder.block.len() == block_len {
+                encoder.encode_frame(&mut target)?;
+            }
+            Ok::<_, ExportError>(())
+        })?;
+        encoder.encode_frame(&mut target)?;
+        target.flush()?;
+
+        quantizer.finish(self.clipping)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlacSink;
+    use crate::{
+        export::{signal, ExportError, SourceSink},
+        num::Real,
+        source::SamplesBuffer,
+    };
+    use std::io::Cursor;
+
+    fn round_trip(channels: u16, bits_per_sample: u16, block_size: usize) {
+        let samples = signal(channels, 1000);
+        let mut bytes = Vec::new();
+        FlacSink::default()
+            .bits_per_sample(bits_per_sample)
+            .block_size(block_size)
+            .write(
+                SamplesBuffer::new(channels, 44100, samples.clone()),
+                &mut bytes,
+            )
+            .unwrap();
+
+        let mut reader = claxon::FlacReader::new(Cursor::new(bytes)).unwrap();
+        let info = reader.streaminfo();
+        assert_eq!(info.channels, u32::from(channels));
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.bits_per_sample, u32::from(bits_per_sample));
+
+        let max = ((1i64 << (bits_per_sample - 1)) - 1) as Real;
+        let expected: Vec<i32> = samples
+            .iter()
+            .map(|sample| (sample * max).round() as i32)
+            .collect();
+        let decoded: Vec<i32> =
+            reader.samples().collect::<Result<_, _>>().unwrap();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn round_trip_16_bit_stereo() {
+        round_trip(2, 16, 256);
+    }
+
+    #[test]
+    fn round_trip_24_bit_mono() {
+        round_trip(1, 24, 4096);
+    }
+
+    #[test]
+    fn round_trip_8_bit_multichannel() {
+        round_trip(6, 8, 192);
+    }
+
+    #[test]
+    fn rejects_more_than_eight_channels() {
+        let source = SamplesBuffer::new(9, 44100, signal(9, 10));
+        let mut bytes = Vec::new();
+        match FlacSink::default().write(source, &mut bytes) {
+            Err(ExportError::UnsupportedChannels { channels }) => {
+                assert_eq!(channels, 9)
+            },
+            other => panic!("expected channel error, got {:?}", other),
+        }
+        assert!(bytes.is_empty());
+    }
+}
@@ -8,3 +8,5 @@ pub mod pitch;
 pub mod note;
 pub mod compass;
 pub mod song;
+pub mod export;
+pub mod analysis;
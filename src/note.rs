@@ -5,6 +5,7 @@ use std::collections::BTreeSet;
 pub enum NoteKind {
     Plain,
     Ligature,
+    Glide,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
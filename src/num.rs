@@ -1,14 +1,29 @@
-use num::{cast::ToPrimitive, rational::Ratio, Num};
-use std::{convert::TryFrom, time::Duration};
+use num::{
+    cast::{AsPrimitive, ToPrimitive},
+    rational::Ratio,
+    Num,
+};
+use std::{convert::TryFrom, fmt, time::Duration};
 
-pub use std::{f64 as real, i128 as integer, u128 as natural};
+pub use std::{i128 as integer, u128 as natural};
+
+#[cfg(not(feature = "f32"))]
+pub use std::f64 as real;
+
+#[cfg(feature = "f32")]
+pub use std::f32 as real;
 
 pub type Natural = u128;
 pub type Integer = i128;
 pub type NaturalRatio = Ratio<Natural>;
 pub type Rational = Ratio<Integer>;
+
+#[cfg(not(feature = "f32"))]
 pub type Real = f64;
 
+#[cfg(feature = "f32")]
+pub type Real = f32;
+
 pub trait RatioExt {
     fn approx_to_f64(&self) -> f64;
 
@@ -68,3 +83,39 @@ impl DurationExt for Duration {
         Self::new(secs, subsec_nanos)
     }
 }
+
+pub fn db_to_amplitude(db: Real) -> Real {
+    Real::powf(10.0, db / 20.0)
+}
+
+pub fn amplitude_to_db(amplitude: Real) -> Real {
+    20.0 * amplitude.log10()
+}
+
+pub trait Sample:
+    Copy + Default + PartialOrd + fmt::Debug + Send + Sync + 'static
+{
+    fn from_real(real: Real) -> Self;
+
+    fn to_real(self) -> Real;
+}
+
+impl Sample for f32 {
+    fn from_real(real: Real) -> Self {
+        real.as_()
+    }
+
+    fn to_real(self) -> Real {
+        self.as_()
+    }
+}
+
+impl Sample for f64 {
+    fn from_real(real: Real) -> Self {
+        real.as_()
+    }
+
+    fn to_real(self) -> Real {
+        self.as_()
+    }
+}
@@ -25,6 +25,21 @@ pub enum Key {
 impl Key {
     pub const TOTAL: usize = 12;
 
+    pub const ALL: [Self; Self::TOTAL] = [
+        Key::C,
+        Key::Cs,
+        Key::D,
+        Key::Ds,
+        Key::E,
+        Key::F,
+        Key::Fs,
+        Key::G,
+        Key::Gs,
+        Key::A,
+        Key::As,
+        Key::B,
+    ];
+
     #[allow(non_upper_case_globals)]
     pub const Db: Self = Key::Cs;
 
@@ -75,6 +90,28 @@ impl Pitch {
 
         a5 * Real::powi(2.0, octaves) * note_ratio().powi(notes)
     }
+
+    pub fn from_freq(freq: Real, a5: Real) -> Option<(Self, Real)> {
+        if !(freq > 0.0 && a5 > 0.0) {
+            return None;
+        }
+        let a5_note = Pitch { key: Key::A, octave: 5 };
+        let notes = Key::TOTAL as Real * (freq / a5).log2();
+        let nearest = notes.round();
+        let cents = (notes - nearest) * 100.0;
+
+        let total = Key::TOTAL as i64;
+        let index =
+            a5_note.octave as i64 * total + a5_note.key as i64 + nearest as i64;
+        if index < 0 || index / total > i64::from(u32::MAX) {
+            return None;
+        }
+        let pitch = Pitch {
+            key: Key::ALL[(index % total) as usize],
+            octave: (index / total) as u32,
+        };
+        Some((pitch, cents))
+    }
 }
 
 impl Sub for Pitch {
@@ -87,3 +124,37 @@ impl Sub for Pitch {
         octave * total + note
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, Pitch};
+
+    #[test]
+    fn from_freq_round_trips_freq() {
+        for octave in 0 .. 10 {
+            for &key in Key::ALL.iter() {
+                let pitch = Pitch { key, octave };
+                let (found, cents) =
+                    Pitch::from_freq(pitch.freq(440.0), 440.0).unwrap();
+                assert_eq!(found, pitch);
+                assert!(cents.abs() < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn from_freq_reports_cents() {
+        let a5 = Pitch { key: Key::A, octave: 5 };
+        let (pitch, cents) = Pitch::from_freq(445.0, 440.0).unwrap();
+        assert_eq!(pitch, a5);
+        assert!((cents - 19.56).abs() < 0.01);
+
+        let (pitch, cents) = Pitch::from_freq(432.0, 432.0).unwrap();
+        assert_eq!(pitch, a5);
+        assert!(cents.abs() < 1e-9);
+
+        assert_eq!(Pitch::from_freq(0.0, 440.0), None);
+        assert_eq!(Pitch::from_freq(440.0, -1.0), None);
+        assert_eq!(Pitch::from_freq(1e-9, 440.0), None);
+    }
+}
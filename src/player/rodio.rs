@@ -1,5 +1,5 @@
 use super::Backend;
-use crate::source::Source;
+use crate::{num::Sample, source::Source};
 use std::{fmt, time::Duration};
 
 pub struct Rodio {
@@ -54,7 +54,7 @@ where
     type Item = f32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|elem| elem as f32)
+        self.inner.next().map(f32::from_real)
     }
 }
 
@@ -1,15 +1,24 @@
 use crate::{
     compass::{Compass, InvalidCompass},
-    effects::LinearFadeOutBuilder,
+    effects::{LinearFadeOut, LinearFadeOutBuilder, Take},
     note::{Note, NoteGroup, NoteKind},
     num::{DurationExt, Natural, NaturalRatio, Real},
     pitch::{Key, Pitch},
-    source::Source,
+    source::{duration_frames, Seekable, Source},
     tempo::{Dot, NoteTime, NoteValue, TimeSignature},
-    wave::{Wave, WaveBuilder},
+    wave::{Glide, GlideBuilder, Wave, WaveBuilder},
 };
 use num::{traits::CheckedSub, Zero};
-use std::{collections::BTreeSet, fmt, mem, time::Duration};
+use std::{
+    borrow::Borrow,
+    collections::BTreeSet,
+    fmt,
+    iter,
+    mem,
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
 
 #[derive(Debug, Clone)]
 pub struct SongBuilder {
@@ -185,17 +194,149 @@ impl Song {
     pub fn duration(&self) -> Duration {
         Duration::from_raw_nanos(self.nanos().to_integer())
     }
+
+    pub fn max_polyphony(&self) -> usize {
+        let groups = self
+            .compasses
+            .iter()
+            .flat_map(|compass| &compass.note_groups)
+            .collect::<Vec<_>>();
+        let mut events = Vec::new();
+        let mut start = NaturalRatio::zero();
+
+        for (i, group) in groups.iter().enumerate() {
+            for &note in &group.notes {
+                if note.kind == NoteKind::Ligature {
+                    continue;
+                }
+                let tied = Note { kind: NoteKind::Ligature, ..note };
+                let end = groups[i + 1 ..]
+                    .iter()
+                    .take_while(|next| next.notes.contains(&tied))
+                    .map(|next| next.tempo.nanos())
+                    .sum::<NaturalRatio>()
+                    + start
+                    + group.tempo.nanos();
+                events.push((start, false));
+                events.push((end, true));
+            }
+            start += group.tempo.nanos();
+        }
+        events.sort();
+
+        let mut active = 0;
+        let mut max = 0;
+        for (_, ends) in events {
+            if ends {
+                active -= 1;
+            } else {
+                active += 1;
+                max = max.max(active);
+            }
+        }
+        max
+    }
+}
+
+const MIX_BLOCK: usize = 256;
+
+type Voice<W> = LinearFadeOut<Take<Glide<W>>>;
+
+fn spare_wave<W>(
+    instrument: &mut W,
+    spares: &mut Vec<W::Source>,
+    freq: Real,
+) -> W::Source
+where
+    W: WaveBuilder,
+    W::Source: Wave,
+{
+    instrument.freq(freq);
+    match spares.pop() {
+        Some(mut wave) => {
+            instrument.finish_into(&mut wave);
+            wave
+        },
+        None => instrument.finish(),
+    }
+}
+
+fn sounding<W>(voice: &Voice<W>) -> bool
+where
+    W: Wave,
+{
+    // After a seek, a voice is kept while it has any frame left to play;
+    // a voice of unknown length never ends on its own, so it is kept too.
+    voice.len() != Some(0)
+}
+
+fn release_voice<W>(spares: &mut Vec<W>, voice: Voice<W>)
+where
+    W: Wave,
+{
+    spares.push(voice.into_inner().into_inner().into_inner());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MonoStep {
+    Hold,
+    Glide(Note),
+    Start(Note),
+    Release,
+}
+
+impl MonoStep {
+    fn new(group: &NoteGroup, mono: Option<Pitch>, portamento: bool) -> Self {
+        let held = group.notes.iter().any(|note| {
+            note.kind == NoteKind::Ligature && Some(note.pitch) == mono
+        });
+        let mut fresh =
+            group.notes.iter().filter(|note| note.kind != NoteKind::Ligature);
+
+        match (fresh.next(), fresh.next()) {
+            (None, _) if held => MonoStep::Hold,
+            (Some(&note), None) => {
+                let glides = portamento || note.kind == NoteKind::Glide;
+                if glides && !held && mono.is_some() {
+                    MonoStep::Glide(note)
+                } else {
+                    MonoStep::Start(note)
+                }
+            },
+            _ => MonoStep::Release,
+        }
+    }
+
+    fn continues(self) -> bool {
+        matches!(self, MonoStep::Hold | MonoStep::Glide(_))
+    }
+}
+
+struct Mono<W>
+where
+    W: Wave,
+{
+    pitch: Pitch,
+    start: usize,
+    voice: Voice<W>,
 }
 
 #[derive(Debug, Clone)]
 pub struct PlayableSongBuilder {
     a5: Real,
     start_compass: usize,
+    portamento: bool,
+    glide_time: Duration,
 }
 
 impl Default for PlayableSongBuilder {
     fn default() -> Self {
-        Self { a5: 440.0, start_compass: 0 }
+        Self {
+            a5: 440.0,
+            start_compass: 0,
+            portamento: false,
+            glide_time: Duration::from_millis(50),
+        }
     }
 }
 
@@ -218,45 +359,227 @@ impl PlayableSongBuilder {
         self.start_compass
     }
 
+    pub fn portamento(&mut self, portamento: bool) -> &mut Self {
+        self.portamento = portamento;
+        self
+    }
+
+    pub fn get_portamento(&self) -> bool {
+        self.portamento
+    }
+
+    pub fn glide_time(&mut self, glide_time: Duration) -> &mut Self {
+        self.glide_time = glide_time;
+        self
+    }
+
+    pub fn get_glide_time(&self) -> Duration {
+        self.glide_time
+    }
+
     pub fn finish<W>(&self, song: Song, instrument: W) -> PlayableSong<W>
     where
         W: WaveBuilder + Send + Sync,
         W::Source: Wave + 'static,
     {
+        let polyphony = song.max_polyphony();
+        self.start(song, instrument, polyphony)
+    }
+
+    pub fn render_parallel<W>(
+        &self,
+        song: &Song,
+        instrument: W,
+        threads: usize,
+    ) -> Vec<Real>
+    where
+        W: WaveBuilder + Clone + Send + Sync,
+        W::Source: Wave + 'static,
+    {
+        let starts = self.compass_starts(song, instrument.get_sample_rate());
+        let compasses = starts.len() - 1;
+        let cuts = (0 .. compasses)
+            .filter(|&compass| !starts[compass].legato)
+            .chain(iter::once(compasses))
+            .collect::<Vec<_>>();
+        let polyphony = song.max_polyphony();
+        let threads = threads.max(1);
+        let jobs = (threads * 4).min(cuts.len() - 1);
+        let bounds = |job: usize| cuts[job * (cuts.len() - 1) / jobs];
+
+        let mut output = vec![0.0; starts[compasses].offset];
+        let mut chunks = Vec::with_capacity(jobs);
+        let mut rest = &mut output[..];
+        for job in 0 .. jobs {
+            let (first, last) = (bounds(job), bounds(job + 1));
+            let len = starts[last].offset - starts[first].offset;
+            let (chunk, tail) = mem::take(&mut rest).split_at_mut(len);
+            chunks.push((first, last, chunk));
+            rest = tail;
+        }
+        let chunks = Mutex::new(chunks.into_iter());
+
+        let mut tails = thread::scope(|scope| {
+            let workers = (0 .. threads.min(jobs))
+                .map(|_| {
+                    let instrument = instrument.clone();
+                    let starts = &starts;
+                    let chunks = &chunks;
+                    scope.spawn(move || {
+                        let mut tails = Vec::new();
+                        loop {
+                            let next = chunks.lock().unwrap().next();
+                            let (first, last, chunk) = match next {
+                                Some(job) => job,
+                                None => break tails,
+                            };
+                            let mut playable =
+                                self.start(song, instrument.clone(), polyphony);
+                            playable.curr_compass = self.start_compass + first;
+                            playable.end_compass = self.start_compass + last;
+                            playable.tails = true;
+                            playable.correction = starts[first].correction;
+                            playable.fill(chunk);
+                            tails
+                                .push((starts[last].offset, playable.render()));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        tails.sort_by_key(|&(offset, _)| offset);
+        for (offset, tail) in tails {
+            let rest = output.get_mut(offset ..).unwrap_or(&mut []);
+            for (out, sample) in rest.iter_mut().zip(tail) {
+                *out += sample;
+            }
+        }
+        output
+    }
+
+    fn start<W, S>(
+        &self,
+        song: S,
+        instrument: W,
+        polyphony: usize,
+    ) -> PlayableSong<W, S>
+    where
+        W: WaveBuilder + Send + Sync,
+        W::Source: Wave + 'static,
+        S: Borrow<Song>,
+    {
+        // A voice may outlive its note by a few frames, overlapping the
+        // voices of the next group; the spares cover those and the mono voice.
+        let slots = polyphony * 2;
+        let spares = (0 ..= slots).map(|_| instrument.finish()).collect();
         PlayableSong {
             a5: self.a5,
             instrument,
-            total_remaining: song.nanos(),
+            total_nanos: song.borrow().nanos(),
+            elapsed: 0,
             correction: NaturalRatio::zero(),
             group_remaining: 0,
             song,
+            start_compass: self.start_compass,
             curr_compass: self.start_compass,
+            end_compass: usize::MAX,
             curr_group: 0,
-            sources: Vec::new(),
+            tails: false,
+            portamento: self.portamento,
+            glide_time: self.glide_time,
+            mono: None,
+            voices: Vec::with_capacity(slots),
+            spares,
         }
     }
+
+    fn compass_starts(
+        &self,
+        song: &Song,
+        sample_rate: u32,
+    ) -> Vec<CompassStart> {
+        let one_sec = Duration::from_secs(1).as_nanos();
+        let sample_nanos = NaturalRatio::new(one_sec, sample_rate as Natural);
+        let mut start = CompassStart {
+            offset: 0,
+            correction: NaturalRatio::zero(),
+            legato: false,
+        };
+        let compasses =
+            song.compasses.get(self.start_compass ..).unwrap_or(&[]);
+        let mut starts = Vec::with_capacity(compasses.len() + 1);
+        let mut mono = None;
+
+        for compass in compasses {
+            start.legato = match compass.note_groups.first() {
+                Some(group) => {
+                    MonoStep::new(group, mono, self.portamento).continues()
+                },
+                None => mono.is_some(),
+            };
+            starts.push(start);
+            for group in &compass.note_groups {
+                mono = match MonoStep::new(group, mono, self.portamento) {
+                    MonoStep::Hold => mono,
+                    MonoStep::Glide(note) | MonoStep::Start(note) => {
+                        Some(note.pitch)
+                    },
+                    MonoStep::Release => None,
+                };
+                let nanos = group.tempo.nanos() + start.correction;
+                let samples = (nanos / sample_nanos).to_integer() as usize;
+                start.offset += samples.max(1);
+                start.correction = nanos.fract();
+            }
+        }
+        starts.push(start);
+        starts
+    }
 }
 
-pub struct PlayableSong<W>
+#[derive(Debug, Clone, Copy)]
+struct CompassStart {
+    offset: usize,
+    correction: NaturalRatio,
+    legato: bool,
+}
+
+pub struct PlayableSong<W, S = Song>
 where
     W: WaveBuilder + Send + Sync,
     W::Source: Wave + 'static,
+    S: Borrow<Song>,
 {
     a5: Real,
-    song: Song,
+    song: S,
     instrument: W,
-    total_remaining: NaturalRatio,
+    total_nanos: NaturalRatio,
+    elapsed: usize,
     correction: NaturalRatio,
     group_remaining: usize,
+    start_compass: usize,
     curr_compass: usize,
+    end_compass: usize,
     curr_group: usize,
-    sources: Vec<Box<dyn Source>>,
+    tails: bool,
+    portamento: bool,
+    glide_time: Duration,
+    mono: Option<Mono<W::Source>>,
+    voices: Vec<Voice<W::Source>>,
+    spares: Vec<W::Source>,
 }
 
-impl<W> PlayableSong<W>
+impl<W, S> PlayableSong<W, S>
 where
     W: WaveBuilder + Send + Sync,
     W::Source: Wave + 'static,
+    S: Borrow<Song> + Send + Sync,
 {
     fn note_nanos(&self, note: Note) -> NaturalRatio {
         let mut curr_group = self.curr_group;
@@ -265,7 +588,7 @@ where
         let mut target = note;
 
         loop {
-            let compass = match self.song.compasses.get(curr_compass) {
+            let compass = match self.song.borrow().compasses.get(curr_compass) {
                 Some(comp) => comp,
                 None => break nanos,
             };
@@ -287,27 +610,69 @@ where
         }
     }
 
-    fn make_samples(&mut self) -> Vec<f64> {
-        let mut samples = Vec::with_capacity(self.sources.len());
+    fn make_glide(
+        &self,
+        wave: W::Source,
+        duration: Duration,
+    ) -> Glide<W::Source> {
+        GlideBuilder::default()
+            .from(wave.freq())
+            .duration(duration)
+            .finish(wave)
+    }
+
+    fn make_voice(
+        &self,
+        glide: Glide<W::Source>,
+        frames: usize,
+    ) -> Voice<W::Source> {
+        LinearFadeOutBuilder::default()
+            .final_vol(0.5)
+            .finish(glide.take_samples(frames))
+    }
+
+    fn note_frames(&self, note: Note, sample_rate: u32) -> usize {
+        let nanos = self.note_nanos(note);
+        let total = (nanos + self.correction).to_integer();
+        duration_frames(Duration::from_raw_nanos(total), sample_rate)
+    }
+
+    fn mix_voices(&mut self) -> Real {
+        let mut sum = 0.0;
         let mut i = 0;
 
-        while let Some(source) = self.sources.get_mut(i) {
-            if let Some(sample) = source.next() {
-                samples.push(sample);
+        while let Some(voice) = self.voices.get_mut(i) {
+            if let Some(sample) = voice.next() {
+                sum += sample;
                 i += 1;
             } else {
-                self.sources.remove(i);
+                let voice = self.voices.swap_remove(i);
+                release_voice(&mut self.spares, voice);
             }
         }
-        samples
+
+        if let Some(mut mono) = self.mono.take() {
+            match mono.voice.next() {
+                Some(sample) => {
+                    sum += sample;
+                    self.mono = Some(mono);
+                },
+                None => release_voice(&mut self.spares, mono.voice),
+            }
+        }
+        sum
     }
 
-    fn next_group(&mut self, samples: &mut Vec<f64>) -> bool {
+    fn next_group(&mut self, skip: Option<usize>) -> bool {
         let group = loop {
-            let compass = match self.song.compasses.get(self.curr_compass) {
-                Some(compass) => compass,
-                None => return false,
-            };
+            if self.curr_compass >= self.end_compass {
+                return false;
+            }
+            let compass =
+                match self.song.borrow().compasses.get(self.curr_compass) {
+                    Some(compass) => compass,
+                    None => return false,
+                };
 
             match compass.note_groups.get(self.curr_group) {
                 Some(group) => break group,
@@ -318,26 +683,83 @@ where
             }
         };
 
-        for &note in &group.notes {
-            if note.kind != NoteKind::Ligature {
-                let nanos = self.note_nanos(note);
-                let total = (nanos + self.correction).to_integer();
-                let duration = Duration::from_raw_nanos(total);
-                let mut source = Box::new(
-                    LinearFadeOutBuilder::default().final_vol(0.5).finish(
-                        self.instrument
-                            .freq(note.pitch.freq(self.a5))
-                            .finish()
-                            .take_duration(duration),
-                    ),
-                );
-                if let Some(sample) = source.next() {
-                    samples.push(sample);
-                    self.sources.push(source);
+        let mono = self.mono.as_ref().map(|mono| mono.pitch);
+        let step = MonoStep::new(group, mono, self.portamento);
+        if !step.continues() {
+            if let Some(mono) = self.mono.take() {
+                if skip.is_none() || sounding(&mono.voice) {
+                    self.voices.push(mono.voice);
+                } else {
+                    release_voice(&mut self.spares, mono.voice);
                 }
             }
         }
 
+        match step {
+            MonoStep::Hold => (),
+            MonoStep::Glide(note) | MonoStep::Start(note) => {
+                let freq = note.pitch.freq(self.a5);
+                let glide = match self.mono.take() {
+                    Some(mut mono) => {
+                        if skip.is_some() {
+                            mono.voice.seek(self.elapsed - mono.start);
+                        }
+                        let mut glide = mono.voice.into_inner().into_inner();
+                        glide.glide_to(freq);
+                        glide
+                    },
+                    None => {
+                        let wave = spare_wave(
+                            &mut self.instrument,
+                            &mut self.spares,
+                            freq,
+                        );
+                        self.make_glide(wave, self.glide_time)
+                    },
+                };
+                let frames = self.note_frames(note, glide.sample_rate());
+                // Take stops one frame short of its count; the extra frame
+                // keeps the voice sounding until the next note can glide it.
+                let mut voice = self.make_voice(glide, frames + 1);
+                if let Some(frames) = skip {
+                    voice.seek(frames);
+                }
+                self.mono = Some(Mono {
+                    pitch: note.pitch,
+                    start: self.elapsed,
+                    voice,
+                });
+            },
+            MonoStep::Release => {
+                for &note in &group.notes {
+                    if note.kind != NoteKind::Ligature {
+                        let freq = note.pitch.freq(self.a5);
+                        let wave = spare_wave(
+                            &mut self.instrument,
+                            &mut self.spares,
+                            freq,
+                        );
+                        let glide =
+                            self.make_glide(wave, Duration::from_secs(0));
+                        let frames =
+                            self.note_frames(note, glide.sample_rate());
+                        let mut voice = self.make_voice(glide, frames);
+                        match skip {
+                            Some(frames) => {
+                                voice.seek(frames);
+                                if sounding(&voice) {
+                                    self.voices.push(voice);
+                                } else {
+                                    release_voice(&mut self.spares, voice);
+                                }
+                            },
+                            None => self.voices.push(voice),
+                        }
+                    }
+                }
+            },
+        }
+
         self.curr_group += 1;
         let nanos = group.tempo.nanos() + self.correction;
         let one_sec = Duration::from_secs(1).as_nanos();
@@ -348,69 +770,135 @@ where
 
         true
     }
+
+    fn render(&mut self) -> Vec<Real> {
+        let mut samples = Vec::new();
+        let mut buf = [0.0; MIX_BLOCK];
+        loop {
+            let len = self.fill(&mut buf);
+            samples.extend_from_slice(&buf[.. len]);
+            if len < buf.len() {
+                break samples;
+            }
+        }
+    }
+
+    fn total_remaining(&self) -> NaturalRatio {
+        let one_sec = Duration::from_secs(1).as_nanos();
+        let time = NaturalRatio::new(one_sec, self.sample_rate() as Natural)
+            * NaturalRatio::from(self.elapsed as Natural);
+        self.total_nanos.checked_sub(&time).unwrap_or(NaturalRatio::zero())
+    }
+
+    fn mix_block(&mut self, buf: &mut [Real]) {
+        let mut scratch = [0.0; MIX_BLOCK];
+
+        for block in buf.chunks_mut(MIX_BLOCK) {
+            for sample in block.iter_mut() {
+                *sample = 0.0;
+            }
+
+            let scratch = &mut scratch[.. block.len()];
+            let mut i = 0;
+            while let Some(voice) = self.voices.get_mut(i) {
+                let len = voice.fill(scratch);
+                for (sample, voice) in block.iter_mut().zip(&scratch[.. len]) {
+                    *sample += voice;
+                }
+                if len < block.len() {
+                    let voice = self.voices.swap_remove(i);
+                    release_voice(&mut self.spares, voice);
+                } else {
+                    i += 1;
+                }
+            }
+
+            if let Some(mut mono) = self.mono.take() {
+                let len = mono.voice.fill(scratch);
+                for (sample, voice) in block.iter_mut().zip(&scratch[.. len]) {
+                    *sample += voice;
+                }
+                if len < block.len() {
+                    release_voice(&mut self.spares, mono.voice);
+                } else {
+                    self.mono = Some(mono);
+                }
+            }
+        }
+
+        self.group_remaining -= buf.len();
+        self.elapsed += buf.len();
+    }
 }
 
-impl<W> fmt::Debug for PlayableSong<W>
+impl<W, S> fmt::Debug for PlayableSong<W, S>
 where
     W: WaveBuilder + Send + Sync + fmt::Debug,
     W::Source: Wave + 'static,
+    S: Borrow<Song> + Send + Sync,
 {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("PlayableSong")
             .field("a5", &self.a5)
-            .field("song", &self.song)
+            .field("song", self.song.borrow())
             .field("instrument", &self.instrument)
-            .field("total_remaining", &self.total_remaining)
+            .field("total_remaining", &self.total_remaining())
             .field("correction", &self.correction)
             .field("group_remaining", &self.group_remaining)
+            .field("start_compass", &self.start_compass)
             .field("curr_compass", &self.curr_compass)
+            .field("end_compass", &self.end_compass)
             .field("curr_group", &self.curr_group)
-            .field("sources", &self.sources.len())
+            .field("tails", &self.tails)
+            .field("portamento", &self.portamento)
+            .field("glide_time", &self.glide_time)
+            .field("mono", &self.mono.as_ref().map(|mono| mono.pitch))
+            .field("voices", &self.voices.len())
             .finish()
     }
 }
 
-impl<W> Iterator for PlayableSong<W>
+impl<W, S> Iterator for PlayableSong<W, S>
 where
     W: WaveBuilder + Send + Sync,
     W::Source: Wave + 'static,
+    S: Borrow<Song> + Send + Sync,
 {
-    type Item = f64;
+    type Item = Real;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut samples = self.make_samples();
-
-        if self.group_remaining == 0 {
-            if !self.next_group(&mut samples) {
+        if self.group_remaining == 0 && !self.next_group(None) {
+            if !self.tails {
                 return None;
             }
+            let sum = self.mix_voices();
+            if self.voices.is_empty() && self.mono.is_none() {
+                return None;
+            }
+            return Some(sum);
         }
+        let sum = self.mix_voices();
         self.group_remaining = self.group_remaining.saturating_sub(1);
+        self.elapsed += 1;
 
-        let one_sec = Duration::from_secs(1).as_nanos();
-        let time = NaturalRatio::new(one_sec, self.sample_rate() as Natural);
-        self.total_remaining = self
-            .total_remaining
-            .checked_sub(&time)
-            .unwrap_or(NaturalRatio::zero());
-
-        Some(samples.iter().sum::<f64>())
+        Some(sum)
     }
 }
 
-impl<W> Source for PlayableSong<W>
+impl<W, S> Source for PlayableSong<W, S>
 where
     W: WaveBuilder + Send + Sync,
     W::Source: Wave + 'static,
+    S: Borrow<Song> + Send + Sync,
 {
     fn len(&self) -> Option<usize> {
-        let nanos = self.total_remaining;
+        let nanos = self.total_remaining();
         let rate = NaturalRatio::from(self.sample_rate() as Natural);
         Some((nanos / rate).to_integer() as usize)
     }
 
     fn duration(&self) -> Option<Duration> {
-        let nanos = self.total_remaining.to_integer();
+        let nanos = self.total_remaining().to_integer();
         Some(Duration::from_raw_nanos(nanos))
     }
 
@@ -421,4 +909,305 @@ where
     fn channels(&self) -> u16 {
         self.instrument.get_channels()
     }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let mut len = 0;
+        while len < buf.len() {
+            if self.group_remaining == 0 {
+                match self.next() {
+                    Some(sample) => buf[len] = sample,
+                    None => break,
+                }
+                len += 1;
+            } else {
+                let block = self.group_remaining.min(buf.len() - len);
+                self.mix_block(&mut buf[len .. len + block]);
+                len += block;
+            }
+        }
+        len
+    }
+}
+
+impl<W, S> Seekable for PlayableSong<W, S>
+where
+    W: WaveBuilder + Send + Sync,
+    W::Source: Wave + 'static,
+    S: Borrow<Song> + Send + Sync,
+{
+    fn seek(&mut self, frame: usize) {
+        self.elapsed = 0;
+        self.correction = NaturalRatio::zero();
+        self.group_remaining = 0;
+        self.curr_compass = self.start_compass;
+        self.curr_group = 0;
+        if let Some(mono) = self.mono.take() {
+            release_voice(&mut self.spares, mono.voice);
+        }
+        for voice in self.voices.drain(..) {
+            release_voice(&mut self.spares, voice);
+        }
+
+        while self.elapsed < frame {
+            let skip = frame - self.elapsed;
+            if !self.next_group(Some(skip)) {
+                break;
+            }
+            let len = self.group_remaining.max(1);
+            if len > skip {
+                self.group_remaining -= skip;
+                self.elapsed = frame;
+            } else {
+                self.group_remaining = 0;
+                self.elapsed += len;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MonoStep, PlayableSongBuilder, Song, SongBuilder};
+    use crate::{
+        note::{Note, NoteKind},
+        num::{Natural, NaturalRatio, Real},
+        pitch::{Key, Pitch},
+        source::{Seekable, Source},
+        tempo::{Dot, NoteValue, TimeSignature},
+        wave::{SawWaveBuilder, SineWaveBuilder},
+    };
+    use std::time::Duration;
+
+    fn song() -> Song {
+        let mut builder = SongBuilder::default();
+        builder
+            .bpm(NoteValue::Quarter, NaturalRatio::from(190))
+            .signature(TimeSignature { numer: 3, denom: NoteValue::Quarter })
+            .dot(Dot::None);
+
+        let keys = [Key::C, Key::Ds, Key::G, Key::As, Key::F, Key::D];
+        for (i, &key) in keys.iter().enumerate() {
+            let bass = Pitch { octave: 3, key };
+            builder
+                .note_kind(NoteKind::Plain)
+                .note_value(NoteValue::Quarter)
+                .pitch(bass)
+                .note()
+                .pitch(Pitch { octave: 5, key: keys[(i + 2) % keys.len()] })
+                .note()
+                .note_group()
+                .note_kind(NoteKind::Ligature)
+                .pitch(bass)
+                .note()
+                .note_kind(NoteKind::Glide)
+                .note_value(NoteValue::Eighth)
+                .pitch(Pitch { octave: 5, key })
+                .note()
+                .note_group()
+                .note_kind(NoteKind::Plain)
+                .pitch(Pitch { octave: 6, key })
+                .note()
+                .note_group()
+                .note_value(NoteValue::Quarter)
+                .note_kind(NoteKind::Ligature)
+                .note()
+                .note_group()
+                .compass();
+        }
+        builder.clear_finish()
+    }
+
+    fn quarters(bpm: Natural, groups: &[&[(NoteKind, Pitch)]]) -> Song {
+        let mut builder = SongBuilder::default();
+        builder
+            .bpm(NoteValue::Quarter, NaturalRatio::from(bpm))
+            .signature(TimeSignature { numer: 4, denom: NoteValue::Quarter })
+            .note_value(NoteValue::Quarter)
+            .dot(Dot::None);
+
+        for compass in groups.chunks(4) {
+            for group in compass {
+                for &(kind, pitch) in group.iter() {
+                    builder.note_kind(kind).pitch(pitch).note();
+                }
+                builder.note_group();
+            }
+            builder.compass();
+        }
+        builder.clear_finish()
+    }
+
+    fn rising_crossings(samples: &[Real]) -> Vec<Real> {
+        samples
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[0] < 0.0 && pair[1] >= 0.0)
+            .map(|(i, pair)| i as Real + pair[0] / (pair[0] - pair[1]))
+            .collect()
+    }
+
+    #[test]
+    fn mono_voice_steps() {
+        let a5 = Pitch { octave: 5, key: Key::A };
+        let c6 = Pitch { octave: 6, key: Key::C };
+        let e6 = Pitch { octave: 6, key: Key::E };
+        let song = quarters(
+            120,
+            &[
+                &[(NoteKind::Plain, a5)],
+                &[(NoteKind::Plain, c6)],
+                &[(NoteKind::Ligature, c6)],
+                &[(NoteKind::Glide, e6), (NoteKind::Plain, a5)],
+                &[(NoteKind::Glide, c6)],
+                &[],
+                &[(NoteKind::Glide, a5)],
+                &[(NoteKind::Glide, e6)],
+            ],
+        );
+        let glide =
+            |pitch| MonoStep::Glide(Note { kind: NoteKind::Glide, pitch });
+        let start = |kind, pitch| MonoStep::Start(Note { kind, pitch });
+
+        for &portamento in &[false, true] {
+            let mut mono = None;
+            let mut steps = Vec::new();
+            for compass in &song.compasses {
+                for group in &compass.note_groups {
+                    let step = MonoStep::new(group, mono, portamento);
+                    mono = match step {
+                        MonoStep::Hold => mono,
+                        MonoStep::Glide(note) | MonoStep::Start(note) => {
+                            Some(note.pitch)
+                        },
+                        MonoStep::Release => None,
+                    };
+                    steps.push(step);
+                }
+            }
+
+            let second = match portamento {
+                false => start(NoteKind::Plain, c6),
+                true => {
+                    MonoStep::Glide(Note { kind: NoteKind::Plain, pitch: c6 })
+                },
+            };
+            assert_eq!(
+                steps,
+                [
+                    start(NoteKind::Plain, a5),
+                    second,
+                    MonoStep::Hold,
+                    MonoStep::Release,
+                    start(NoteKind::Glide, c6),
+                    MonoStep::Release,
+                    start(NoteKind::Glide, a5),
+                    glide(e6),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn glided_notes_keep_phase_across_boundaries() {
+        let a5 = Pitch { octave: 5, key: Key::A };
+        let note: &[_] = &[(NoteKind::Glide, a5)];
+        let song = quarters(130, &[note; 4]);
+        let period = 48000.0 / a5.freq(440.0);
+
+        let samples = PlayableSongBuilder::default()
+            .finish(song, SineWaveBuilder::default())
+            .collect::<Vec<_>>();
+        let crossings = rising_crossings(&samples);
+        assert!(crossings.len() > 800);
+        for pair in crossings.windows(2) {
+            assert!((pair[1] - pair[0] - period).abs() < 0.05);
+        }
+
+        let mut builder = PlayableSongBuilder::default();
+        builder.portamento(false);
+        let note: &[_] = &[(NoteKind::Plain, a5)];
+        let plain = quarters(130, &[note; 4]);
+        let samples = builder
+            .finish(plain, SineWaveBuilder::default())
+            .collect::<Vec<_>>();
+        let crossings = rising_crossings(&samples);
+        assert!(crossings
+            .windows(2)
+            .any(|pair| (pair[1] - pair[0] - period).abs() > 1.0));
+    }
+
+    #[test]
+    fn seek_matches_skipped_render() {
+        let mut builder = PlayableSongBuilder::default();
+        builder.portamento(true).glide_time(Duration::from_millis(20));
+        let full = builder
+            .finish(song(), SineWaveBuilder::default())
+            .collect::<Vec<_>>();
+
+        let frames = [0, 1, 4000, 12345, 30000, full.len() - 10, full.len()];
+        for &frame in &frames {
+            let mut playable =
+                builder.finish(song(), SineWaveBuilder::default());
+            playable.seek(frame);
+            let sought = playable.collect::<Vec<_>>();
+            assert_eq!(sought.len(), full.len() - frame);
+            for (sought, full) in sought.iter().zip(&full[frame ..]) {
+                assert!((sought - full).abs() < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_render_matches_sequential() {
+        let mut builder = PlayableSongBuilder::default();
+        builder.portamento(true).glide_time(Duration::from_millis(20));
+
+        for &start_compass in &[0, 2] {
+            builder.start_compass(start_compass);
+            let mut playable =
+                builder.finish(song(), SawWaveBuilder::default());
+            let mut sequential = Vec::new();
+            let mut buf = [0.0; 300];
+            loop {
+                let len = playable.fill(&mut buf);
+                sequential.extend_from_slice(&buf[.. len]);
+                if len < buf.len() {
+                    break;
+                }
+            }
+
+            for &threads in &[1, 2, 3, 8] {
+                let parallel = builder.render_parallel(
+                    &song(),
+                    SawWaveBuilder::default(),
+                    threads,
+                );
+                assert_eq!(parallel.len(), sequential.len());
+                for (parallel, sequential) in parallel.iter().zip(&sequential) {
+                    assert!((parallel - sequential).abs() < 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_render_is_deterministic() {
+        let builder = PlayableSongBuilder::default();
+        let song = song();
+        for &threads in &[2, 3, 4, 8] {
+            let first = builder.render_parallel(
+                &song,
+                SawWaveBuilder::default(),
+                threads,
+            );
+            for _ in 0 .. 3 {
+                let again = builder.render_parallel(
+                    &song,
+                    SawWaveBuilder::default(),
+                    threads,
+                );
+                assert!(again == first);
+            }
+        }
+    }
 }
@@ -1,9 +1,37 @@
+mod buffer;
+mod looping;
+
+pub use buffer::{Buffered, SamplesBuffer};
+pub use looping::{LoopMode, LoopRegion, LoopRegionBuilder, Repeat};
+
 use crate::{
-    effects::{LinearFadeOut, LinearFadeOutBuilder, Take},
-    num::{Natural, NaturalRatio, Real},
+    analysis::Tap,
+    effects::{
+        AmplitudeMod,
+        AmplitudeModBuilder,
+        Crossfade,
+        DcBlocker,
+        DcBlockerBuilder,
+        FadeIn,
+        FadeInBuilder,
+        FadeOut,
+        FadeOutBuilder,
+        Gain,
+        LinearFadeOut,
+        LinearFadeOutBuilder,
+        Normalize,
+        RingMod,
+        Skip,
+        Take,
+    },
+    export::{ExportError, ExportStats, WavExportOptions},
+    num::{db_to_amplitude, DurationExt, Natural, NaturalRatio, Real, Sample},
 };
 use std::{
-    io::{Seek, Write},
+    fmt,
+    fs::File,
+    io::{self, BufReader, Read, Seek, Write},
+    path::Path,
     time::Duration,
 };
 
@@ -20,6 +48,15 @@ pub trait Source: Iterator<Item = Real> + Send + Sync {
         48000
     }
 
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let mut len = 0;
+        for (slot, sample) in buf.iter_mut().zip(self) {
+            *slot = sample;
+            len += 1;
+        }
+        len
+    }
+
     fn fade_out(self) -> LinearFadeOut<Self>
     where
         Self: Sized,
@@ -27,6 +64,49 @@ pub trait Source: Iterator<Item = Real> + Send + Sync {
         LinearFadeOutBuilder::default().finish(self)
     }
 
+    fn fade_in(self, duration: Duration) -> FadeIn<Self>
+    where
+        Self: Sized,
+    {
+        FadeInBuilder::default().duration(duration).finish(self)
+    }
+
+    fn fade_out_duration(self, duration: Duration) -> FadeOut<Self>
+    where
+        Self: Sized,
+    {
+        FadeOutBuilder::default().duration(duration).finish(self)
+    }
+
+    fn gain(self, db: Real) -> Gain<Self>
+    where
+        Self: Sized,
+    {
+        Gain::new(self, db_to_amplitude(db))
+    }
+
+    fn normalize(self, target: Normalize) -> Option<Gain<SamplesBuffer>>
+    where
+        Self: Sized,
+    {
+        self.len()?;
+        Some(target.apply(SamplesBuffer::from_source(self)))
+    }
+
+    fn dc_block(self) -> DcBlocker<Self>
+    where
+        Self: Sized,
+    {
+        DcBlockerBuilder::default().finish(self)
+    }
+
+    fn tap(self) -> Tap<Self>
+    where
+        Self: Sized,
+    {
+        Tap::new(self)
+    }
+
     fn take_samples(self, samples: usize) -> Take<Self>
     where
         Self: Sized,
@@ -38,12 +118,68 @@ pub trait Source: Iterator<Item = Real> + Send + Sync {
     where
         Self: Sized,
     {
-        let sample_time = NaturalRatio::new(
-            Duration::from_secs(1).as_nanos(),
-            self.sample_rate() as Natural,
-        );
-        let nanos = NaturalRatio::from(duration.as_nanos());
-        self.take_samples((nanos / sample_time).round().to_integer() as usize)
+        let frames = duration_frames(duration, self.sample_rate());
+        self.take_samples(frames)
+    }
+
+    fn repeat(self, times: usize) -> Repeat<Self>
+    where
+        Self: Sized + Clone,
+    {
+        Repeat::new(self, Some(times))
+    }
+
+    fn loop_forever(self) -> Repeat<Self>
+    where
+        Self: Sized + Clone,
+    {
+        Repeat::new(self, None)
+    }
+
+    fn buffered(self) -> Buffered<Self>
+    where
+        Self: Sized,
+    {
+        Buffered::new(self)
+    }
+
+    fn skip_samples(self, samples: usize) -> Skip<Self>
+    where
+        Self: Sized,
+    {
+        Skip::new(self, samples)
+    }
+
+    fn skip_duration(self, duration: Duration) -> Skip<Self>
+    where
+        Self: Sized,
+    {
+        let frames = duration_frames(duration, self.sample_rate());
+        self.skip_samples(frames)
+    }
+
+    fn crossfade<B>(self, other: B, duration: Duration) -> Crossfade<Self, B>
+    where
+        Self: Sized,
+        B: Source,
+    {
+        Crossfade::new(self, other, duration)
+    }
+
+    fn ring_mod<M>(self, modulator: M) -> RingMod<Self, M>
+    where
+        Self: Sized,
+        M: Source,
+    {
+        RingMod::new(self, modulator)
+    }
+
+    fn amplitude_mod<M>(self, modulator: M) -> AmplitudeMod<Self, M>
+    where
+        Self: Sized,
+        M: Source,
+    {
+        AmplitudeModBuilder::default().finish(self, modulator)
     }
 
     fn to_wav<W>(self, target: W) -> Result<(), hound::Error>
@@ -62,12 +198,40 @@ pub trait Source: Iterator<Item = Real> + Send + Sync {
         )?;
 
         for sample in self {
-            writer.write_sample(sample as f32)?;
+            writer.write_sample(f32::from_real(sample))?;
         }
 
         writer.flush()?;
         Ok(())
     }
+
+    fn to_wav_with<W>(
+        self,
+        target: W,
+        options: &WavExportOptions,
+    ) -> Result<ExportStats, ExportError>
+    where
+        Self: Sized,
+        W: Write + Seek,
+    {
+        options.write(self, target)
+    }
+}
+
+pub trait Seekable: Source {
+    fn seek(&mut self, frame: usize);
+
+    fn seek_duration(&mut self, duration: Duration) {
+        let frames = duration_frames(duration, self.sample_rate());
+        self.seek(frames);
+    }
+
+    fn loop_region(self, start: usize, end: usize) -> LoopRegion<Self>
+    where
+        Self: Sized,
+    {
+        LoopRegionBuilder::default().start(start).end(Some(end)).finish(self)
+    }
 }
 
 impl<'this, S> Source for &'this mut S
@@ -89,6 +253,10 @@ where
     fn sample_rate(&self) -> u32 {
         (**self).sample_rate()
     }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        (**self).fill(buf)
+    }
 }
 
 impl<S> Source for Box<S>
@@ -110,6 +278,65 @@ where
     fn sample_rate(&self) -> u32 {
         (**self).sample_rate()
     }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        (**self).fill(buf)
+    }
+}
+
+impl<'this, S> Seekable for &'this mut S
+where
+    S: Seekable,
+{
+    fn seek(&mut self, frame: usize) {
+        (**self).seek(frame)
+    }
+}
+
+impl<S> Seekable for Box<S>
+where
+    S: Seekable + ?Sized,
+{
+    fn seek(&mut self, frame: usize) {
+        (**self).seek(frame)
+    }
+}
+
+pub(crate) fn duration_frames(duration: Duration, sample_rate: u32) -> usize {
+    let sample_time = NaturalRatio::new(
+        Duration::from_secs(1).as_nanos(),
+        sample_rate as Natural,
+    );
+    let nanos = NaturalRatio::from(duration.as_nanos());
+    (nanos / sample_time).round().to_integer() as usize
+}
+
+pub(crate) fn frames_duration(frames: usize, sample_rate: u32) -> Duration {
+    let nanos = frames as u128 * Duration::from_secs(1).as_nanos()
+        / u128::from(sample_rate.max(1));
+    Duration::from_raw_nanos(nanos)
+}
+
+pub(crate) fn option_min<I, T>(init: Option<T>, iterable: I) -> Option<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    T: Ord,
+{
+    let mut ret = init;
+
+    for elem in iterable {
+        if let Some(curr) = ret {
+            if let Some(val) = elem.filter(|val| val < &curr) {
+                ret = Some(val);
+            } else {
+                ret = Some(curr);
+            }
+        } else {
+            ret = elem;
+        }
+    }
+
+    ret
 }
 
 pub trait SourceBuilder {
@@ -120,6 +347,10 @@ pub trait SourceBuilder {
     fn get_sample_rate(&self) -> u32;
 
     fn finish(&self) -> Self::Source;
+
+    fn finish_into(&self, source: &mut Self::Source) {
+        *source = self.finish();
+    }
 }
 
 impl<'builder, B> SourceBuilder for &'builder mut B
@@ -139,6 +370,10 @@ where
     fn finish(&self) -> Self::Source {
         (**self).finish()
     }
+
+    fn finish_into(&self, source: &mut Self::Source) {
+        (**self).finish_into(source)
+    }
 }
 
 impl<B> SourceBuilder for Box<B>
@@ -158,6 +393,10 @@ where
     fn finish(&self) -> Self::Source {
         (**self).finish()
     }
+
+    fn finish_into(&self, source: &mut Self::Source) {
+        (**self).finish_into(source)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -192,6 +431,10 @@ impl Source for Silence {
     }
 }
 
+impl Seekable for Silence {
+    fn seek(&mut self, _frame: usize) {}
+}
+
 #[derive(Debug, Clone)]
 pub struct SilenceBuilder {
     sample_rate: u32,
@@ -231,3 +474,214 @@ impl SourceBuilder for SilenceBuilder {
         Silence { sample_rate: self.sample_rate, channels: self.channels }
     }
 }
+
+pub struct WavSource<R>
+where
+    R: Read,
+{
+    reader: hound::WavReader<R>,
+    spec: hound::WavSpec,
+    remaining: usize,
+    scale: Real,
+    error: Option<hound::Error>,
+}
+
+impl<R> WavSource<R>
+where
+    R: Read,
+{
+    pub fn new(reader: R) -> Result<Self, hound::Error> {
+        let reader = hound::WavReader::new(reader)?;
+        let spec = reader.spec();
+        let bits = i32::from(spec.bits_per_sample.max(1)) - 1;
+        Ok(Self {
+            remaining: reader.len() as usize,
+            scale: Real::powi(2.0, -bits),
+            reader,
+            spec,
+            error: None,
+        })
+    }
+
+    pub fn spec(&self) -> hound::WavSpec {
+        self.spec
+    }
+
+    pub fn error(&self) -> Option<&hound::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn take_error(&mut self) -> Option<hound::Error> {
+        self.error.take()
+    }
+
+    fn fail(&mut self, error: hound::Error) -> Option<Real> {
+        self.remaining = 0;
+        self.error = Some(error);
+        None
+    }
+}
+
+impl WavSource<BufReader<File>> {
+    pub fn open<P>(path: P) -> Result<Self, hound::Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R> fmt::Debug for WavSource<R>
+where
+    R: Read,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("WavSource")
+            .field("spec", &self.spec)
+            .field("remaining", &self.remaining)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl<R> Iterator for WavSource<R>
+where
+    R: Read + Send + Sync,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let sample = match self.spec.sample_format {
+            hound::SampleFormat::Float => self
+                .reader
+                .samples::<f32>()
+                .next()
+                .map(|sample| sample.map(|sample| sample as Real)),
+            hound::SampleFormat::Int => {
+                let scale = self.scale;
+                self.reader
+                    .samples::<i32>()
+                    .next()
+                    .map(|sample| sample.map(|sample| sample as Real * scale))
+            },
+        };
+        match sample {
+            Some(Ok(sample)) => {
+                self.remaining -= 1;
+                Some(sample)
+            },
+            Some(Err(error)) => self.fail(error),
+            None => {
+                let eof = io::Error::from(io::ErrorKind::UnexpectedEof);
+                self.fail(hound::Error::IoError(eof))
+            },
+        }
+    }
+}
+
+impl<R> Source for WavSource<R>
+where
+    R: Read + Send + Sync,
+{
+    fn len(&self) -> Option<usize> {
+        Some(self.remaining / usize::from(self.spec.channels.max(1)))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(frames_duration(self.len()?, self.spec.sample_rate))
+    }
+
+    fn channels(&self) -> u16 {
+        self.spec.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.spec.sample_rate
+    }
+}
+
+impl<R> Seekable for WavSource<R>
+where
+    R: Read + Seek + Send + Sync,
+{
+    fn seek(&mut self, frame: usize) {
+        let frames = self.reader.duration() as usize;
+        let frame = frame.min(frames);
+        let channels = usize::from(self.spec.channels.max(1));
+        match self.reader.seek(frame as u32) {
+            Ok(()) => self.remaining = (frames - frame) * channels,
+            Err(error) => {
+                self.fail(hound::Error::IoError(error));
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SamplesBuffer, Source, WavSource};
+    use crate::{
+        export::{SampleFormat, WavExportOptions},
+        num::Real,
+    };
+    use std::io::Cursor;
+
+    fn ramp() -> Vec<Real> {
+        (0 .. 200).map(|i| (i as Real / 100.0 - 1.0) * 0.9).collect()
+    }
+
+    #[test]
+    fn wav_float_round_trip() {
+        let mut bytes = Cursor::new(Vec::new());
+        SamplesBuffer::new(2, 44100, ramp()).to_wav(&mut bytes).unwrap();
+
+        let source = WavSource::new(Cursor::new(bytes.into_inner())).unwrap();
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.sample_rate(), 44100);
+        assert_eq!(source.len(), Some(100));
+        let decoded: Vec<Real> = source.collect();
+        assert_eq!(decoded.len(), 200);
+        for (decoded, original) in decoded.iter().zip(ramp()) {
+            assert!((decoded - original).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn wav_int_round_trip() {
+        let mut bytes = Cursor::new(Vec::new());
+        SamplesBuffer::new(1, 48000, ramp())
+            .to_wav_with(
+                &mut bytes,
+                WavExportOptions::default()
+                    .bits_per_sample(16)
+                    .sample_format(SampleFormat::Int),
+            )
+            .unwrap();
+
+        let mut source =
+            WavSource::new(Cursor::new(bytes.into_inner())).unwrap();
+        let decoded: Vec<Real> = (&mut source).collect();
+        assert!(source.error().is_none());
+        assert_eq!(decoded.len(), 200);
+        for (decoded, original) in decoded.iter().zip(ramp()) {
+            assert!((decoded - original).abs() < 1.0 / 16384.0);
+        }
+    }
+
+    #[test]
+    fn truncated_wav_reports_error() {
+        let mut bytes = Cursor::new(Vec::new());
+        SamplesBuffer::new(1, 48000, ramp()).to_wav(&mut bytes).unwrap();
+        let mut bytes = bytes.into_inner();
+        bytes.truncate(bytes.len() - 10);
+
+        let mut source = WavSource::new(Cursor::new(bytes)).unwrap();
+        let decoded = (&mut source).count();
+        assert!(decoded < 200);
+        assert!(source.error().is_some());
+    }
+}
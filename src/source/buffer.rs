@@ -0,0 +1,344 @@
+use super::{frames_duration, Seekable, Source};
+use crate::num::{Real, Sample};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+const BUFFER_BLOCK: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct SamplesBuffer<T = Real>
+where
+    T: Sample,
+{
+    samples: Arc<[T]>,
+    channels: u16,
+    sample_rate: u32,
+    position: usize,
+}
+
+impl<T> SamplesBuffer<T>
+where
+    T: Sample,
+{
+    pub fn new(channels: u16, sample_rate: u32, samples: Vec<T>) -> Self {
+        Self { samples: samples.into(), channels, sample_rate, position: 0 }
+    }
+
+    pub fn from_source<S>(source: S) -> Self
+    where
+        S: Source,
+    {
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        Self::new(channels, sample_rate, source.map(T::from_real).collect())
+    }
+
+    pub fn samples(&self) -> &[T] {
+        &self.samples
+    }
+
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.samples.get(index).copied()
+    }
+
+    pub fn frame(&self, index: usize) -> Option<&[T]> {
+        let channels = usize::from(self.channels.max(1));
+        let start = index.checked_mul(channels)?;
+        self.samples.get(start .. start + channels)
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position.min(self.samples.len());
+    }
+
+    pub fn reverse(&self) -> Self {
+        let channels = usize::from(self.channels.max(1));
+        let remaining = &self.samples[self.position ..];
+        let whole = remaining.len() / channels * channels;
+        let reversed = remaining[.. whole]
+            .chunks_exact(channels)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        Self::new(self.channels, self.sample_rate, reversed)
+    }
+}
+
+impl<T> Iterator for SamplesBuffer<T>
+where
+    T: Sample,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.get(self.position)?;
+        self.position += 1;
+        Some(sample.to_real())
+    }
+}
+
+impl<T> Source for SamplesBuffer<T>
+where
+    T: Sample,
+{
+    fn len(&self) -> Option<usize> {
+        let remaining = self.samples.len() - self.position;
+        Some(remaining / usize::from(self.channels.max(1)))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(frames_duration(self.len()?, self.sample_rate))
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let remaining = &self.samples[self.position ..];
+        let len = buf.len().min(remaining.len());
+        for (out, sample) in buf.iter_mut().zip(&remaining[.. len]) {
+            *out = sample.to_real();
+        }
+        self.position += len;
+        len
+    }
+}
+
+impl<T> Seekable for SamplesBuffer<T>
+where
+    T: Sample,
+{
+    fn seek(&mut self, frame: usize) {
+        let channels = usize::from(self.channels.max(1));
+        self.set_position(frame.saturating_mul(channels));
+    }
+}
+
+#[derive(Debug)]
+struct Shared<S, T>
+where
+    S: Source,
+    T: Sample,
+{
+    source: Option<S>,
+    block: Vec<Real>,
+    samples: Vec<T>,
+}
+
+impl<S, T> Shared<S, T>
+where
+    S: Source,
+    T: Sample,
+{
+    fn load(&mut self, position: usize) -> bool {
+        while position >= self.samples.len() {
+            let source = match &mut self.source {
+                Some(source) => source,
+                None => return false,
+            };
+            let len = source.fill(&mut self.block);
+            let block = self.block[.. len].iter();
+            self.samples.extend(block.map(|&sample| T::from_real(sample)));
+            if len < BUFFER_BLOCK {
+                self.block = Vec::new();
+                self.source = None;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug)]
+pub struct Buffered<S, T = Real>
+where
+    S: Source,
+    T: Sample,
+{
+    shared: Arc<Mutex<Shared<S, T>>>,
+    channels: u16,
+    sample_rate: u32,
+    position: usize,
+}
+
+impl<S, T> Buffered<S, T>
+where
+    S: Source,
+    T: Sample,
+{
+    pub fn new(source: S) -> Self {
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        let shared = Shared {
+            source: Some(source),
+            block: vec![0.0; BUFFER_BLOCK],
+            samples: Vec::new(),
+        };
+        Self {
+            shared: Arc::new(Mutex::new(shared)),
+            channels,
+            sample_rate,
+            position: 0,
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: usize) {
+        let mut shared = self.lock();
+        shared.load(position);
+        let loaded = shared.samples.len();
+        drop(shared);
+        self.position = position.min(loaded);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared<S, T>> {
+        self.shared.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl<S, T> Clone for Buffered<S, T>
+where
+    S: Source,
+    T: Sample,
+{
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            position: self.position,
+        }
+    }
+}
+
+impl<S, T> Iterator for Buffered<S, T>
+where
+    S: Source,
+    T: Sample,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut shared = self.lock();
+        if !shared.load(self.position) {
+            return None;
+        }
+        let sample = shared.samples[self.position];
+        drop(shared);
+        self.position += 1;
+        Some(sample.to_real())
+    }
+}
+
+impl<S, T> Source for Buffered<S, T>
+where
+    S: Source,
+    T: Sample,
+{
+    fn len(&self) -> Option<usize> {
+        let shared = self.lock();
+        let channels = usize::from(self.channels.max(1));
+        let buffered = (shared.samples.len() - self.position) / channels;
+        match &shared.source {
+            Some(source) => source.len().map(|len| len + buffered),
+            None => Some(buffered),
+        }
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(frames_duration(self.len()?, self.sample_rate))
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let mut shared = self.lock();
+        shared.load(self.position + buf.len().saturating_sub(1));
+        let remaining = &shared.samples[self.position ..];
+        let len = buf.len().min(remaining.len());
+        for (out, sample) in buf.iter_mut().zip(&remaining[.. len]) {
+            *out = sample.to_real();
+        }
+        drop(shared);
+        self.position += len;
+        len
+    }
+}
+
+impl<S, T> Seekable for Buffered<S, T>
+where
+    S: Source,
+    T: Sample,
+{
+    fn seek(&mut self, frame: usize) {
+        let channels = usize::from(self.channels.max(1));
+        self.set_position(frame.saturating_mul(channels));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Buffered, SamplesBuffer};
+    use crate::source::{Seekable, Source};
+
+    #[test]
+    fn f32_buffers_alongside_f64() {
+        let samples = vec![0.1, -0.2, 0.3, -0.4, 0.5, -0.6];
+        let wide = SamplesBuffer::new(2, 44100, samples.clone());
+        let narrow = SamplesBuffer::<f32>::from_source(wide.clone());
+        assert_eq!(narrow.samples(), &[0.1, -0.2, 0.3, -0.4, 0.5, -0.6]);
+        assert_eq!(narrow.channels(), 2);
+        assert_eq!(narrow.len(), Some(3));
+
+        let mut buffered = Buffered::<_, f32>::new(wide);
+        let mut buf = [0.0; 4];
+        assert_eq!(buffered.fill(&mut buf), 4);
+        buffered.seek(1);
+        let rest: Vec<_> = buffered.clone().collect();
+        assert_eq!(rest.len(), 4);
+
+        let narrow: Vec<_> = narrow.collect();
+        for (narrow, wide) in narrow.iter().zip(&samples) {
+            assert!((narrow - wide).abs() < 1e-7);
+        }
+        for (rest, wide) in rest.iter().zip(&samples[2 ..]) {
+            assert!((rest - wide).abs() < 1e-7);
+        }
+    }
+
+    #[test]
+    fn reverse_keeps_channels_aligned() {
+        let samples = vec![1.0, -1.0, 2.0, -2.0, 3.0, -3.0, 4.0];
+        let mut buffer = SamplesBuffer::new(2, 8000, samples);
+        assert_eq!(
+            buffer.reverse().samples(),
+            &[3.0, -3.0, 2.0, -2.0, 1.0, -1.0]
+        );
+
+        buffer.seek(1);
+        let reversed = buffer.reverse();
+        assert_eq!(reversed.samples(), &[3.0, -3.0, 2.0, -2.0]);
+        assert_eq!(reversed.channels(), 2);
+        assert_eq!(reversed.sample_rate(), 8000);
+    }
+}
@@ -0,0 +1,519 @@
+use super::{duration_frames, frames_duration, Seekable, Source};
+use crate::num::Real;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct Repeat<S>
+where
+    S: Source + Clone,
+{
+    source: S,
+    current: S,
+    remaining: Option<usize>,
+}
+
+impl<S> Repeat<S>
+where
+    S: Source + Clone,
+{
+    pub(crate) fn new(source: S, times: Option<usize>) -> Self {
+        Self { current: source.clone(), source, remaining: times }
+    }
+}
+
+impl<S> Iterator for Repeat<S>
+where
+    S: Source + Clone,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+        if let Some(sample) = self.current.next() {
+            return Some(sample);
+        }
+
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+        if self.remaining == Some(0) {
+            return None;
+        }
+        self.current = self.source.clone();
+        self.current.next()
+    }
+}
+
+impl<S> Source for Repeat<S>
+where
+    S: Source + Clone,
+{
+    fn len(&self) -> Option<usize> {
+        match self.remaining {
+            Some(0) => Some(0),
+            Some(remaining) => {
+                let rest = self.source.len()? * (remaining - 1);
+                Some(self.current.len()? + rest)
+            },
+            None => None,
+        }
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(frames_duration(self.len()?, self.sample_rate()))
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopMode {
+    Forward,
+    PingPong,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoopRegion<S>
+where
+    S: Seekable,
+{
+    inner: S,
+    mode: LoopMode,
+    start: usize,
+    end: usize,
+    crossfade: usize,
+    remaining: Option<usize>,
+    position: usize,
+    pre_roll: Vec<Real>,
+    region: Vec<Real>,
+    cursor: Option<(usize, bool)>,
+    frame: Vec<Real>,
+    channel: usize,
+}
+
+impl<S> LoopRegion<S>
+where
+    S: Seekable,
+{
+    fn looping(&self) -> bool {
+        self.remaining != Some(0) && self.end > self.start
+    }
+
+    fn region_frames(&self) -> usize {
+        self.end - self.start
+    }
+
+    fn round_trip(&self) -> usize {
+        let frames = self.region_frames();
+        (2 * frames - 2).max(frames)
+    }
+
+    fn next_loop(&mut self) {
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+    }
+
+    fn next_buffered(&mut self, index: usize, forward: bool) {
+        let channels = self.frame.len();
+        let start = index * channels;
+        self.frame.copy_from_slice(&self.region[start .. start + channels]);
+
+        let frames = self.region_frames();
+        self.cursor = if !forward {
+            Some(if index <= 1 { (0, true) } else { (index - 1, false) })
+        } else if index + 1 < frames {
+            Some((index + 1, true))
+        } else {
+            self.next_loop();
+            if !self.looping() {
+                None
+            } else if frames > 2 {
+                Some((frames - 2, false))
+            } else {
+                Some((0, true))
+            }
+        };
+    }
+
+    fn next_frame(&mut self) -> bool {
+        if let Some((index, forward)) = self.cursor {
+            self.next_buffered(index, forward);
+            return true;
+        }
+
+        let channels = self.frame.len();
+        for channel in 0 .. channels {
+            match self.inner.next() {
+                Some(sample) => self.frame[channel] = sample,
+                None => return false,
+            }
+        }
+
+        let position = self.position;
+        self.position += 1;
+        if !self.looping() {
+            return true;
+        }
+
+        match self.mode {
+            LoopMode::Forward => {
+                let fade_start = self.end - self.crossfade;
+                let full = self.crossfade * channels;
+                if position + self.crossfade >= self.start
+                    && position < self.start
+                    && self.pre_roll.len() < full
+                {
+                    self.pre_roll.extend_from_slice(&self.frame);
+                }
+                if position >= fade_start
+                    && position < self.end
+                    && self.pre_roll.len() == full
+                {
+                    let index = position - fade_start;
+                    let ratio =
+                        (index + 1) as Real / (self.crossfade + 1) as Real;
+                    let head = &self.pre_roll[index * channels ..];
+                    for (sample, head) in self.frame.iter_mut().zip(head) {
+                        *sample = *sample * (1.0 - ratio) + head * ratio;
+                    }
+                }
+                if position + 1 == self.end {
+                    self.inner.seek(self.start);
+                    self.position = self.start;
+                    self.next_loop();
+                }
+            },
+
+            LoopMode::PingPong => {
+                let full = self.region_frames() * channels;
+                if position >= self.start && self.region.len() < full {
+                    self.region.extend_from_slice(&self.frame);
+                }
+                if position + 1 == self.end && self.region.len() == full {
+                    let frames = self.region_frames();
+                    self.cursor = if frames > 2 {
+                        Some((frames - 2, false))
+                    } else {
+                        Some((0, true))
+                    };
+                }
+            },
+        }
+
+        true
+    }
+}
+
+impl<S> Iterator for LoopRegion<S>
+where
+    S: Seekable,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.channel == 0 && !self.next_frame() {
+            return None;
+        }
+        let sample = self.frame[self.channel];
+        self.channel = (self.channel + 1) % self.frame.len();
+        Some(sample)
+    }
+}
+
+impl<S> Source for LoopRegion<S>
+where
+    S: Seekable,
+{
+    fn len(&self) -> Option<usize> {
+        let rest = self.inner.len()?;
+        if !self.looping() {
+            return Some(rest);
+        }
+
+        let remaining = self.remaining?;
+        let frames = self.region_frames();
+        let len = match (self.mode, self.cursor) {
+            (LoopMode::Forward, _) => rest + remaining * frames,
+            (LoopMode::PingPong, None) => rest + remaining * self.round_trip(),
+            (LoopMode::PingPong, Some((index, forward))) => {
+                let pass =
+                    if forward { frames - index } else { index + frames };
+                rest + pass + (remaining - 1) * self.round_trip()
+            },
+        };
+        Some(len)
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(frames_duration(self.len()?, self.sample_rate()))
+    }
+
+    fn channels(&self) -> u16 {
+        self.frame.len() as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoopRegionBuilder {
+    start: usize,
+    end: Option<usize>,
+    crossfade: Duration,
+    count: Option<usize>,
+    mode: LoopMode,
+}
+
+impl Default for LoopRegionBuilder {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            end: None,
+            crossfade: Duration::from_secs(0),
+            count: None,
+            mode: LoopMode::Forward,
+        }
+    }
+}
+
+impl LoopRegionBuilder {
+    pub fn start(&mut self, start: usize) -> &mut Self {
+        self.start = start;
+        self
+    }
+
+    pub fn end(&mut self, end: Option<usize>) -> &mut Self {
+        self.end = end;
+        self
+    }
+
+    pub fn crossfade(&mut self, crossfade: Duration) -> &mut Self {
+        self.crossfade = crossfade;
+        self
+    }
+
+    pub fn count(&mut self, count: Option<usize>) -> &mut Self {
+        self.count = count;
+        self
+    }
+
+    pub fn mode(&mut self, mode: LoopMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn get_start(&self) -> usize {
+        self.start
+    }
+
+    pub fn get_end(&self) -> Option<usize> {
+        self.end
+    }
+
+    pub fn get_crossfade(&self) -> Duration {
+        self.crossfade
+    }
+
+    pub fn get_count(&self) -> Option<usize> {
+        self.count
+    }
+
+    pub fn get_mode(&self) -> LoopMode {
+        self.mode
+    }
+
+    pub fn finish<S>(&self, mut source: S) -> LoopRegion<S>
+    where
+        S: Seekable,
+    {
+        source.seek(0);
+        let channels = usize::from(source.channels().max(1));
+        let end = match (self.end, source.len()) {
+            (Some(end), Some(len)) => end.min(len),
+            (end, len) => end.or(len).unwrap_or(0),
+        };
+        let start = self.start.min(end);
+        let crossfade = duration_frames(self.crossfade, source.sample_rate())
+            .min(start)
+            .min(end - start);
+        LoopRegion {
+            inner: source,
+            mode: self.mode,
+            start,
+            end,
+            crossfade,
+            remaining: self.count,
+            position: 0,
+            pre_roll: Vec::with_capacity(crossfade * channels),
+            region: Vec::new(),
+            cursor: None,
+            frame: vec![0.0; channels],
+            channel: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoopMode, LoopRegionBuilder};
+    use crate::{
+        num::Real,
+        source::{SamplesBuffer, Seekable, Source},
+    };
+    use std::time::Duration;
+
+    fn ramp(channels: u16, frames: usize) -> SamplesBuffer {
+        let samples = (0 .. frames * usize::from(channels))
+            .map(|sample| sample as Real)
+            .collect();
+        SamplesBuffer::new(channels, 1000, samples)
+    }
+
+    fn region(
+        start: usize,
+        end: usize,
+        count: Option<usize>,
+        mode: LoopMode,
+    ) -> LoopRegionBuilder {
+        let mut builder = LoopRegionBuilder::default();
+        builder.start(start).end(Some(end)).count(count).mode(mode);
+        builder
+    }
+
+    #[test]
+    fn forward_repeats_region_count_times() {
+        let forward = |count| region(3, 6, count, LoopMode::Forward);
+        let once = forward(Some(1)).finish(ramp(1, 8)).collect::<Vec<_>>();
+        assert_eq!(
+            once,
+            [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 3.0, 4.0, 5.0, 6.0, 7.0]
+        );
+
+        let twice = forward(Some(2)).finish(ramp(1, 8)).collect::<Vec<_>>();
+        assert_eq!(
+            twice,
+            [
+                0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 3.0, 4.0, 5.0, 3.0, 4.0, 5.0,
+                6.0, 7.0,
+            ]
+        );
+
+        let mut forever = forward(None).finish(ramp(1, 8));
+        assert_eq!(forever.len(), None);
+        let head = forever.by_ref().take(15).collect::<Vec<_>>();
+        assert_eq!(
+            head,
+            [
+                0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 3.0, 4.0, 5.0, 3.0, 4.0, 5.0,
+                3.0, 4.0, 5.0,
+            ]
+        );
+        assert_eq!(forever.len(), None);
+    }
+
+    #[test]
+    fn crossfade_blends_pre_roll_into_seam() {
+        let mut builder = region(4, 8, Some(1), LoopMode::Forward);
+        builder.crossfade(Duration::from_millis(2));
+        let output = builder.finish(ramp(1, 10)).collect::<Vec<_>>();
+        let expected = [
+            0.0,
+            1.0,
+            2.0,
+            3.0,
+            4.0,
+            5.0,
+            6.0 * 2.0 / 3.0 + 2.0 / 3.0,
+            7.0 / 3.0 + 3.0 * 2.0 / 3.0,
+            4.0,
+            5.0,
+            6.0,
+            7.0,
+            8.0,
+            9.0,
+        ];
+        assert_eq!(output.len(), expected.len());
+        for (output, expected) in output.iter().zip(&expected) {
+            assert!((output - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn ping_pong_reverses_at_region_edges() {
+        let ping_pong = |count| region(2, 5, count, LoopMode::PingPong);
+        let once = ping_pong(Some(1)).finish(ramp(1, 7)).collect::<Vec<_>>();
+        assert_eq!(
+            once,
+            [0.0, 1.0, 2.0, 3.0, 4.0, 3.0, 2.0, 3.0, 4.0, 5.0, 6.0,]
+        );
+
+        let twice = ping_pong(Some(2)).finish(ramp(2, 7)).collect::<Vec<_>>();
+        let frames = twice.chunks(2).map(|frame| frame[1] / 2.0);
+        assert!(twice.chunks(2).all(|frame| frame[1] == frame[0] + 1.0));
+        assert_eq!(
+            frames.collect::<Vec<_>>(),
+            [
+                0.5, 1.5, 2.5, 3.5, 4.5, 3.5, 2.5, 3.5, 4.5, 3.5, 2.5, 3.5,
+                4.5, 5.5, 6.5,
+            ]
+        );
+    }
+
+    #[test]
+    fn len_matches_emitted_frames() {
+        let regions = [(3, 6), (0, 8), (4, 5), (4, 6), (6, 20)];
+        for &mode in &[LoopMode::Forward, LoopMode::PingPong] {
+            for &count in &[Some(0), Some(1), Some(2)] {
+                for &(start, end) in &regions {
+                    for &channels in &[1, 2] {
+                        let mut builder = region(start, end, count, mode);
+                        builder.crossfade(Duration::from_millis(2));
+                        let mut looped = builder.finish(ramp(channels, 8));
+                        let mut lens = vec![looped.len().unwrap()];
+                        let mut samples = 0;
+                        while looped.next().is_some() {
+                            samples += 1;
+                            if samples % usize::from(channels) == 0 {
+                                lens.push(looped.len().unwrap());
+                            }
+                        }
+                        let frames = samples / usize::from(channels);
+                        assert_eq!(lens.len(), frames + 1);
+                        for (emitted, len) in lens.into_iter().enumerate() {
+                            assert_eq!(len, frames - emitted);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn region_positions_are_absolute() {
+        let mut source = ramp(1, 8);
+        source.seek(5);
+        source.next();
+        let looped = region(3, 6, Some(1), LoopMode::Forward)
+            .finish(source)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            looped,
+            [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 3.0, 4.0, 5.0, 6.0, 7.0,]
+        );
+
+        let past_end = region(6, 100, Some(1), LoopMode::Forward)
+            .finish(ramp(1, 8))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            past_end,
+            [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 6.0, 7.0,]
+        );
+    }
+}
@@ -1,11 +1,25 @@
 use crate::{
-    num::{real::consts::PI, Real},
-    source::{Source, SourceBuilder},
+    num::{real::consts::PI, Real, Sample},
+    source::{option_min, Seekable, Source, SourceBuilder},
 };
 use std::time::Duration;
 
 pub trait Wave: Source {
     fn freq(&self) -> Real;
+
+    fn set_freq(&mut self, freq: Real);
+
+    fn phase(&self) -> Real;
+
+    fn set_phase(&mut self, phase: Real);
+
+    fn reset_phase(&mut self) {
+        self.set_phase(0.0);
+    }
+}
+
+fn frame_phase(frame: usize, freq: Real) -> Real {
+    (frame as f64 * f64::from_real(freq) / 48000.0).fract().to_real()
 }
 
 pub trait WaveBuilder
@@ -63,18 +77,21 @@ where
 #[derive(Debug, Clone)]
 pub struct SineWave {
     freq: Real,
-    index: usize,
+    phase: Real,
+}
+
+impl SineWave {
+    fn advance(&mut self, step: Real) -> Real {
+        self.phase = (self.phase + step).fract();
+        (PI * 2.0 * self.phase).sin()
+    }
 }
 
 impl Iterator for SineWave {
     type Item = Real;
 
     fn next(&mut self) -> Option<Real> {
-        self.index = self.index.wrapping_add(1);
-
-        let index = self.index as Real;
-        let coeficient = PI * 2.0 * self.freq;
-        Some((coeficient * index / 48000.0).sin())
+        Some(self.advance(self.freq / 48000.0))
     }
 }
 
@@ -94,12 +111,38 @@ impl Source for SineWave {
     fn sample_rate(&self) -> u32 {
         48000
     }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let step = self.freq / 48000.0;
+        for sample in buf.iter_mut() {
+            *sample = self.advance(step);
+        }
+        buf.len()
+    }
 }
 
 impl Wave for SineWave {
     fn freq(&self) -> Real {
         self.freq
     }
+
+    fn set_freq(&mut self, freq: Real) {
+        self.freq = freq;
+    }
+
+    fn phase(&self) -> Real {
+        self.phase
+    }
+
+    fn set_phase(&mut self, phase: Real) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+}
+
+impl Seekable for SineWave {
+    fn seek(&mut self, frame: usize) {
+        self.set_phase(frame_phase(frame, self.freq));
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -126,7 +169,7 @@ impl SourceBuilder for SineWaveBuilder {
     }
 
     fn finish(&self) -> Self::Source {
-        SineWave { freq: self.freq, index: 0 }
+        SineWave { freq: self.freq, phase: 0.0 }
     }
 }
 
@@ -152,19 +195,22 @@ pub struct SawWave {
     freq: Real,
 }
 
+impl SawWave {
+    fn advance(&mut self, period: Real) -> Real {
+        self.index += 1.0;
+        if self.index >= period {
+            self.index -= period;
+        }
+
+        self.index / period * 2.0 - 1.0
+    }
+}
+
 impl Iterator for SawWave {
     type Item = Real;
 
     fn next(&mut self) -> Option<Real> {
-        let period = 48000.0 / self.freq;
-        if self.index > period {
-            self.index -= period;
-        } else {
-            self.index += 1.0;
-        }
-
-        let value = self.index / period * 2.0 - 1.0;
-        Some(value)
+        Some(self.advance(48000.0 / self.freq))
     }
 }
 
@@ -184,12 +230,40 @@ impl Source for SawWave {
     fn sample_rate(&self) -> u32 {
         48000
     }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let period = 48000.0 / self.freq;
+        for sample in buf.iter_mut() {
+            *sample = self.advance(period);
+        }
+        buf.len()
+    }
 }
 
 impl Wave for SawWave {
     fn freq(&self) -> Real {
         self.freq
     }
+
+    fn set_freq(&mut self, freq: Real) {
+        let phase = self.phase();
+        self.freq = freq;
+        self.set_phase(phase);
+    }
+
+    fn phase(&self) -> Real {
+        (self.index * self.freq / 48000.0).fract()
+    }
+
+    fn set_phase(&mut self, phase: Real) {
+        self.index = phase.rem_euclid(1.0) * 48000.0 / self.freq;
+    }
+}
+
+impl Seekable for SawWave {
+    fn seek(&mut self, frame: usize) {
+        self.set_phase(frame_phase(frame, self.freq));
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -242,20 +316,27 @@ pub struct SquareWave {
     freq: Real,
 }
 
-impl Iterator for SquareWave {
-    type Item = Real;
-
-    fn next(&mut self) -> Option<Real> {
-        let period = 48000.0 / self.freq;
-        if self.index > period {
+impl SquareWave {
+    fn advance(&mut self, period: Real) -> Real {
+        self.index += 1.0;
+        if self.index >= period {
             self.index -= period;
-        } else {
-            self.index += 1.0;
         }
 
         let ratio = self.index / period;
-        let value = if ratio < 0.5 { 1.0 } else { -1.0 };
-        Some(value)
+        if ratio < 0.5 {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+}
+
+impl Iterator for SquareWave {
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Real> {
+        Some(self.advance(48000.0 / self.freq))
     }
 }
 
@@ -275,12 +356,40 @@ impl Source for SquareWave {
     fn sample_rate(&self) -> u32 {
         48000
     }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let period = 48000.0 / self.freq;
+        for sample in buf.iter_mut() {
+            *sample = self.advance(period);
+        }
+        buf.len()
+    }
 }
 
 impl Wave for SquareWave {
     fn freq(&self) -> Real {
         self.freq
     }
+
+    fn set_freq(&mut self, freq: Real) {
+        let phase = self.phase();
+        self.freq = freq;
+        self.set_phase(phase);
+    }
+
+    fn phase(&self) -> Real {
+        (self.index * self.freq / 48000.0).fract()
+    }
+
+    fn set_phase(&mut self, phase: Real) {
+        self.index = phase.rem_euclid(1.0) * 48000.0 / self.freq;
+    }
+}
+
+impl Seekable for SquareWave {
+    fn seek(&mut self, frame: usize) {
+        self.set_phase(frame_phase(frame, self.freq));
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -333,26 +442,29 @@ pub struct TriangleWave {
     freq: Real,
 }
 
-impl Iterator for TriangleWave {
-    type Item = Real;
-
-    fn next(&mut self) -> Option<Real> {
-        let period = 48000.0 / self.freq;
-        if self.index > period {
+impl TriangleWave {
+    fn advance(&mut self, period: Real) -> Real {
+        self.index += 1.0;
+        if self.index >= period {
             self.index -= period;
-        } else {
-            self.index += 1.0;
         }
 
         let ratio = self.index / period * 4.0;
-        let value = if ratio < 1.0 {
+        if ratio < 1.0 {
             ratio
         } else if ratio < 3.0 {
             2.0 - ratio
         } else {
             ratio - 4.0
-        };
-        Some(value)
+        }
+    }
+}
+
+impl Iterator for TriangleWave {
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Real> {
+        Some(self.advance(48000.0 / self.freq))
     }
 }
 
@@ -372,12 +484,40 @@ impl Source for TriangleWave {
     fn sample_rate(&self) -> u32 {
         48000
     }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let period = 48000.0 / self.freq;
+        for sample in buf.iter_mut() {
+            *sample = self.advance(period);
+        }
+        buf.len()
+    }
 }
 
 impl Wave for TriangleWave {
     fn freq(&self) -> Real {
         self.freq
     }
+
+    fn set_freq(&mut self, freq: Real) {
+        let phase = self.phase();
+        self.freq = freq;
+        self.set_phase(phase);
+    }
+
+    fn phase(&self) -> Real {
+        (self.index * self.freq / 48000.0).fract()
+    }
+
+    fn set_phase(&mut self, phase: Real) {
+        self.index = phase.rem_euclid(1.0) * 48000.0 / self.freq;
+    }
+}
+
+impl Seekable for TriangleWave {
+    fn seek(&mut self, frame: usize) {
+        self.set_phase(frame_phase(frame, self.freq));
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -451,28 +591,6 @@ where
     }
 }
 
-fn option_min<I, T>(init: Option<T>, iterable: I) -> Option<T>
-where
-    I: IntoIterator<Item = Option<T>>,
-    T: Ord,
-{
-    let mut ret = init;
-
-    for elem in iterable {
-        if let Some(curr) = ret {
-            if let Some(val) = elem.filter(|val| val < &curr) {
-                ret = Some(val);
-            } else {
-                ret = Some(curr);
-            }
-        } else {
-            ret = elem;
-        }
-    }
-
-    ret
-}
-
 impl<W> Source for RichWave<W>
 where
     W: Wave,
@@ -507,6 +625,33 @@ where
     fn freq(&self) -> Real {
         self.wave.freq()
     }
+
+    fn set_freq(&mut self, freq: Real) {
+        self.wave.set_freq(freq);
+    }
+
+    fn phase(&self) -> Real {
+        self.wave.phase()
+    }
+
+    fn set_phase(&mut self, phase: Real) {
+        self.wave.set_phase(phase);
+        for helper in &mut self.helpers {
+            helper.set_phase(phase);
+        }
+    }
+}
+
+impl<W> Seekable for RichWave<W>
+where
+    W: Wave + Seekable,
+{
+    fn seek(&mut self, frame: usize) {
+        self.wave.seek(frame);
+        for helper in &mut self.helpers {
+            helper.seek(frame);
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -576,6 +721,14 @@ where
     pub fn get_wet(&self) -> Real {
         self.wet
     }
+
+    fn push_helpers(&self, helpers: &mut Vec<B::Source>) {
+        let leap = (self.max - self.min) / self.depth as Real;
+        for i in 0 .. self.depth {
+            let freq = self.min + i as Real * leap;
+            helpers.push(self.inner.clone().freq(freq).finish());
+        }
+    }
 }
 
 impl<B> SourceBuilder for RichWaveBuilder<B>
@@ -594,16 +747,22 @@ where
     }
 
     fn finish(&self) -> Self::Source {
-        let wave = self.inner.finish();
-        let mut helpers = Vec::with_capacity(self.depth);
-
-        let leap = (self.max - self.min) / self.depth as Real;
-        for i in 0 .. self.depth {
-            let freq = self.min + i as Real * leap;
-            helpers.push(self.inner.clone().freq(freq).finish());
-        }
+        let mut source = RichWave {
+            wave: self.inner.finish(),
+            helpers: Vec::with_capacity(self.depth),
+            dry: self.dry,
+            wet: self.wet,
+        };
+        self.push_helpers(&mut source.helpers);
+        source
+    }
 
-        RichWave { wave, helpers, dry: self.dry, wet: self.wet }
+    fn finish_into(&self, source: &mut Self::Source) {
+        self.inner.finish_into(&mut source.wave);
+        source.helpers.clear();
+        self.push_helpers(&mut source.helpers);
+        source.dry = self.dry;
+        source.wet = self.wet;
     }
 }
 
@@ -626,3 +785,476 @@ where
         self
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct HardSync<M, S>
+where
+    M: Wave,
+    S: Wave,
+{
+    master: M,
+    slave: S,
+    last_phase: Real,
+}
+
+impl<M, S> Iterator for HardSync<M, S>
+where
+    M: Wave,
+    S: Wave,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Real> {
+        self.master.next()?;
+        let phase = self.master.phase();
+        if phase < self.last_phase - 0.5 {
+            self.slave.reset_phase();
+        }
+        self.last_phase = phase;
+        self.slave.next()
+    }
+}
+
+impl<M, S> Source for HardSync<M, S>
+where
+    M: Wave,
+    S: Wave,
+{
+    fn len(&self) -> Option<usize> {
+        option_min(self.master.len(), Some(self.slave.len()))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        option_min(self.master.duration(), Some(self.slave.duration()))
+    }
+
+    fn channels(&self) -> u16 {
+        self.slave.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.slave.sample_rate()
+    }
+}
+
+impl<M, S> Wave for HardSync<M, S>
+where
+    M: Wave,
+    S: Wave,
+{
+    fn freq(&self) -> Real {
+        self.master.freq()
+    }
+
+    fn set_freq(&mut self, freq: Real) {
+        let ratio = self.slave.freq() / self.master.freq();
+        self.master.set_freq(freq);
+        self.slave.set_freq(freq * ratio);
+    }
+
+    fn phase(&self) -> Real {
+        self.master.phase()
+    }
+
+    fn set_phase(&mut self, phase: Real) {
+        let ratio = self.slave.freq() / self.master.freq();
+        self.master.set_phase(phase);
+        self.slave.set_phase(phase.rem_euclid(1.0) * ratio);
+        self.last_phase = self.master.phase();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HardSyncBuilder<M, S>
+where
+    M: WaveBuilder,
+    M::Source: Wave,
+    S: WaveBuilder + Clone,
+    S::Source: Wave,
+{
+    master: M,
+    slave: S,
+    ratio: Real,
+}
+
+impl<M, S> HardSyncBuilder<M, S>
+where
+    M: WaveBuilder,
+    M::Source: Wave,
+    S: WaveBuilder + Clone,
+    S::Source: Wave,
+{
+    pub fn new(master: M, slave: S) -> Self {
+        Self { master, slave, ratio: 2.0 }
+    }
+
+    pub fn ratio(&mut self, ratio: Real) -> &mut Self {
+        self.ratio = ratio;
+        self
+    }
+
+    pub fn get_ratio(&self) -> Real {
+        self.ratio
+    }
+}
+
+impl<M, S> SourceBuilder for HardSyncBuilder<M, S>
+where
+    M: WaveBuilder,
+    M::Source: Wave,
+    S: WaveBuilder + Clone,
+    S::Source: Wave,
+{
+    type Source = HardSync<M::Source, S::Source>;
+
+    fn get_channels(&self) -> u16 {
+        self.slave.get_channels()
+    }
+
+    fn get_sample_rate(&self) -> u32 {
+        self.slave.get_sample_rate()
+    }
+
+    fn finish(&self) -> Self::Source {
+        let master = self.master.finish();
+        let slave_freq = self.master.get_freq() * self.ratio;
+        let slave = self.slave.clone().freq(slave_freq).finish();
+        HardSync { last_phase: master.phase(), master, slave }
+    }
+}
+
+impl<M, S> WaveBuilder for HardSyncBuilder<M, S>
+where
+    M: WaveBuilder,
+    M::Source: Wave,
+    S: WaveBuilder + Clone,
+    S::Source: Wave,
+{
+    fn freq(&mut self, freq: Real) -> &mut Self {
+        self.master.freq(freq);
+        self
+    }
+
+    fn get_freq(&self) -> Real {
+        self.master.get_freq()
+    }
+
+    fn sample_rate(&mut self, sample_rate: u32) -> &mut Self {
+        self.master.sample_rate(sample_rate);
+        self.slave.sample_rate(sample_rate);
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Glide<W>
+where
+    W: Wave,
+{
+    wave: W,
+    from: Real,
+    to: Real,
+    phase: Real,
+    step: usize,
+    steps: usize,
+}
+
+impl<W> Glide<W>
+where
+    W: Wave,
+{
+    pub fn glide_to(&mut self, freq: Real) {
+        self.from = self.wave.freq();
+        self.to = freq;
+        self.phase = self.wave.phase();
+        self.step = 0;
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.wave
+    }
+}
+
+impl<W> Iterator for Glide<W>
+where
+    W: Wave,
+{
+    type Item = Real;
+
+    fn next(&mut self) -> Option<Real> {
+        if self.step < self.steps {
+            let ratio = self.step as Real / self.steps as Real;
+            let freq = self.from * (self.to / self.from).powf(ratio);
+            self.wave.set_freq(freq);
+            self.step += 1;
+        } else if self.step == self.steps {
+            self.wave.set_freq(self.to);
+            self.step += 1;
+        }
+
+        self.wave.next()
+    }
+}
+
+impl<W> Source for Glide<W>
+where
+    W: Wave,
+{
+    fn len(&self) -> Option<usize> {
+        self.wave.len()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.wave.duration()
+    }
+
+    fn channels(&self) -> u16 {
+        self.wave.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.wave.sample_rate()
+    }
+
+    fn fill(&mut self, buf: &mut [Real]) -> usize {
+        let mut len = 0;
+        while self.step <= self.steps && len < buf.len() {
+            match self.next() {
+                Some(sample) => buf[len] = sample,
+                None => return len,
+            }
+            len += 1;
+        }
+        len + self.wave.fill(&mut buf[len ..])
+    }
+}
+
+impl<W> Wave for Glide<W>
+where
+    W: Wave,
+{
+    fn freq(&self) -> Real {
+        self.wave.freq()
+    }
+
+    fn set_freq(&mut self, freq: Real) {
+        self.to = freq;
+        self.step = self.steps + 1;
+        self.wave.set_freq(freq);
+    }
+
+    fn phase(&self) -> Real {
+        self.wave.phase()
+    }
+
+    fn set_phase(&mut self, phase: Real) {
+        self.wave.set_phase(phase);
+    }
+}
+
+impl<W> Seekable for Glide<W>
+where
+    W: Wave,
+{
+    fn seek(&mut self, frame: usize) {
+        let gliding = frame.min(self.steps);
+        let ratio = self.to / self.from;
+        let mut cycles = (0 .. gliding)
+            .map(|step| {
+                let exp = step as Real / self.steps as Real;
+                f64::from_real(self.from * ratio.powf(exp))
+            })
+            .sum::<f64>();
+        cycles += (frame - gliding) as f64 * f64::from_real(self.to);
+
+        self.step = frame.min(self.steps + 1);
+        if frame < self.steps {
+            let exp = frame as Real / self.steps as Real;
+            self.wave.set_freq(self.from * ratio.powf(exp));
+        } else {
+            self.wave.set_freq(self.to);
+        }
+        let rate = f64::from(self.wave.sample_rate());
+        let phase = f64::from_real(self.phase) + cycles / rate;
+        self.wave.set_phase(phase.fract().to_real());
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GlideBuilder {
+    from: Real,
+    duration: Duration,
+}
+
+impl Default for GlideBuilder {
+    fn default() -> Self {
+        Self { from: 440.0, duration: Duration::from_millis(50) }
+    }
+}
+
+impl GlideBuilder {
+    pub fn from(&mut self, from: Real) -> &mut Self {
+        self.from = from;
+        self
+    }
+
+    pub fn duration(&mut self, duration: Duration) -> &mut Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn get_from(&self) -> Real {
+        self.from
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn finish<W>(&self, mut wave: W) -> Glide<W>
+    where
+        W: Wave,
+    {
+        let to = wave.freq();
+        let secs = self.duration.as_secs_f64() as Real;
+        let steps = (secs * wave.sample_rate() as Real).round() as usize;
+        wave.set_freq(self.from);
+        let phase = wave.phase();
+        Glide { wave, from: self.from, to, phase, step: 0, steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        GlideBuilder,
+        SawWaveBuilder,
+        SineWaveBuilder,
+        SquareWaveBuilder,
+        TriangleWaveBuilder,
+        Wave,
+        WaveBuilder,
+    };
+    use crate::{
+        num::Real,
+        source::{Seekable, SourceBuilder},
+    };
+    use std::time::Duration;
+
+    fn phase_diff(a: Real, b: Real) -> Real {
+        ((a - b + 0.5).rem_euclid(1.0) - 0.5).abs()
+    }
+
+    fn assert_seek_matches_playback<W>(wave: W)
+    where
+        W: Wave + Seekable + Clone,
+    {
+        for &frame in &[0, 1, 99, 100, 12345] {
+            let mut sought = wave.clone();
+            sought.seek(frame);
+            let played = wave.clone().skip(frame);
+            for (sought, played) in sought.zip(played).take(300) {
+                assert!((sought - played).abs() < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn oscillator_seek_matches_playback() {
+        assert_seek_matches_playback(
+            SineWaveBuilder::default().freq(441.0).finish(),
+        );
+        assert_seek_matches_playback(
+            SineWaveBuilder::default().freq(480.0).finish(),
+        );
+        assert_seek_matches_playback(
+            SawWaveBuilder::default().freq(480.0).finish(),
+        );
+        assert_seek_matches_playback(
+            SquareWaveBuilder::default().freq(480.0).finish(),
+        );
+        assert_seek_matches_playback(
+            TriangleWaveBuilder::default().freq(480.0).finish(),
+        );
+    }
+
+    #[test]
+    fn glide_follows_exponential_curve() {
+        let wave = SineWaveBuilder::default().freq(880.0).finish();
+        let mut glide = GlideBuilder::default()
+            .from(440.0)
+            .duration(Duration::from_millis(10))
+            .finish(wave);
+        assert_eq!(glide.freq(), 440.0);
+
+        for step in 0 .. 480 {
+            glide.next();
+            let expected = 440.0 * Real::powf(2.0, step as Real / 480.0);
+            assert!((glide.freq() - expected).abs() < 1e-3);
+        }
+        glide.next();
+        assert_eq!(glide.freq(), 880.0);
+        glide.next();
+        assert_eq!(glide.freq(), 880.0);
+    }
+
+    #[test]
+    fn glide_to_continues_from_current_freq_and_phase() {
+        let wave = SineWaveBuilder::default().freq(880.0).finish();
+        let mut glide = GlideBuilder::default()
+            .from(440.0)
+            .duration(Duration::from_millis(10))
+            .finish(wave);
+        for _ in 0 .. 240 {
+            glide.next();
+        }
+        let (freq, phase) = (glide.freq(), glide.phase());
+        assert!((freq - 440.0 * Real::sqrt(2.0)).abs() < 1.0);
+
+        glide.glide_to(220.0);
+        assert_eq!(glide.freq(), freq);
+        assert_eq!(glide.phase(), phase);
+        glide.next();
+        assert_eq!(glide.freq(), freq);
+        for _ in 0 .. 480 {
+            glide.next();
+        }
+        assert_eq!(glide.freq(), 220.0);
+    }
+
+    #[test]
+    fn glide_seek_matches_playback() {
+        let wave = SineWaveBuilder::default().freq(660.0).finish();
+        let mut glide = GlideBuilder::default()
+            .from(330.0)
+            .duration(Duration::from_millis(5))
+            .finish(wave);
+
+        for &frames in &[0, 1, 100, 240, 241, 1000] {
+            let mut played = glide.clone();
+            for _ in 0 .. frames {
+                played.next();
+            }
+            let mut sought = glide.clone();
+            sought.seek(frames);
+            assert!(phase_diff(sought.phase(), played.phase()) < 1e-3);
+            for (sought, played) in sought.zip(played).take(300) {
+                assert!((sought - played).abs() < 1e-2);
+            }
+        }
+
+        for _ in 0 .. 333 {
+            glide.next();
+        }
+        glide.glide_to(990.0);
+        let mut played = glide.clone();
+        for _ in 0 .. 500 {
+            played.next();
+        }
+        glide.seek(500);
+        assert!(phase_diff(glide.phase(), played.phase()) < 1e-3);
+        for (sought, played) in glide.zip(played).take(300) {
+            assert!((sought - played).abs() < 1e-2);
+        }
+    }
+}
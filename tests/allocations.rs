@@ -0,0 +1,128 @@
+use mursic::{
+    note::NoteKind,
+    num::NaturalRatio,
+    pitch::{Key, Pitch},
+    song::{PlayableSongBuilder, Song, SongBuilder},
+    source::Source,
+    tempo::{Dot, NoteValue, TimeSignature},
+    wave::{RichWaveBuilder, SawWaveBuilder, Wave, WaveBuilder},
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(
+        &self,
+        ptr: *mut u8,
+        layout: Layout,
+        new_size: usize,
+    ) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn chords() -> Song {
+    let mut builder = SongBuilder::default();
+    builder
+        .bpm(NoteValue::Quarter, NaturalRatio::from(240))
+        .signature(TimeSignature { numer: 4, denom: NoteValue::Quarter })
+        .note_value(NoteValue::Quarter)
+        .dot(Dot::None);
+
+    for &(root, third) in &[(Key::C, Key::E), (Key::A, Key::C)] {
+        let bass = Pitch { octave: 3, key: root };
+        builder
+            .note_kind(NoteKind::Plain)
+            .pitch(bass)
+            .note()
+            .pitch(Pitch { octave: 5, key: root })
+            .note()
+            .note_group()
+            .pitch(Pitch { octave: 5, key: third })
+            .note()
+            .note_kind(NoteKind::Ligature)
+            .pitch(bass)
+            .note()
+            .note_group()
+            .note()
+            .note_group()
+            .note()
+            .note_kind(NoteKind::Plain)
+            .pitch(Pitch { octave: 6, key: root })
+            .note()
+            .note_group()
+            .compass();
+    }
+    builder.clear_finish()
+}
+
+fn ligatures() -> Song {
+    let mut builder = SongBuilder::default();
+    builder
+        .bpm(NoteValue::Quarter, NaturalRatio::from(240))
+        .signature(TimeSignature { numer: 4, denom: NoteValue::Quarter })
+        .note_value(NoteValue::Quarter)
+        .dot(Dot::None);
+
+    let keys = [Key::C, Key::E, Key::G, Key::B];
+    for (i, &key) in keys.iter().enumerate() {
+        builder.note_kind(NoteKind::Plain).pitch(Pitch { octave: 4, key });
+        builder.note();
+        for &tied in &keys[.. i] {
+            builder
+                .note_kind(NoteKind::Ligature)
+                .pitch(Pitch { octave: 4, key: tied })
+                .note();
+        }
+        builder.note_group();
+    }
+    builder.compass();
+    builder.clear_finish()
+}
+
+fn fill_allocations<W>(song: Song, instrument: W, block: usize) -> usize
+where
+    W: WaveBuilder + Send + Sync,
+    W::Source: Wave + 'static,
+{
+    let mut playable = PlayableSongBuilder::default().finish(song, instrument);
+    let mut buf = vec![0.0; block];
+    let allocations = ALLOCATIONS.load(Relaxed);
+    while playable.fill(&mut buf) == buf.len() {}
+    ALLOCATIONS.load(Relaxed) - allocations
+}
+
+#[test]
+fn song_fill_does_not_allocate() {
+    assert_eq!(chords().max_polyphony(), 4);
+    assert_eq!(ligatures().max_polyphony(), 4);
+
+    let mut rich = RichWaveBuilder::new(SawWaveBuilder::default());
+    rich.depth(8);
+    for &block in &[1, 64, 1000, 4096] {
+        let saw = SawWaveBuilder::default();
+        assert_eq!(fill_allocations(chords(), saw.clone(), block), 0);
+        assert_eq!(fill_allocations(ligatures(), saw, block), 0);
+        assert_eq!(fill_allocations(chords(), rich.clone(), block), 0);
+        assert_eq!(fill_allocations(ligatures(), rich.clone(), block), 0);
+    }
+}